@@ -2,10 +2,10 @@ extern crate cdb;
 #[macro_use]
 extern crate criterion;
 
-use cdb::CDB;
+use cdb::{CDB, FileBuffer};
 use criterion::Criterion;
 
-fn test_cdb() -> CDB {
+fn test_cdb() -> CDB<FileBuffer> {
     CDB::open("tests/test2.cdb").expect("Could not open tests/test2.cdb")
 }
 
@@ -35,6 +35,14 @@ fn reader_benchmark(c: &mut Criterion) {
             }
         })
     });
+    c.bench_function("CDB::find_ref result loop", |b| {
+        let cdb = test_cdb();
+        b.iter(|| {
+            for result in cdb.find_ref(b"one") {
+                result.unwrap();
+            }
+        })
+    });
     c.bench_function("CDB::open + find result loop", |b| {
         b.iter(|| {
             let cdb = test_cdb();
@@ -51,6 +59,14 @@ fn reader_benchmark(c: &mut Criterion) {
             }
         })
     });
+    c.bench_function("CDB::iter_ref result loop", |b| {
+        let cdb = test_cdb();
+        b.iter(|| {
+            for result in cdb.iter_ref() {
+                result.unwrap();
+            }
+        })
+    });
     c.bench_function("CDB::open + iter result loop", |b| {
         b.iter(|| {
             let cdb = test_cdb();
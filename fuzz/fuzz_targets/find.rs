@@ -0,0 +1,32 @@
+#![no_main]
+
+use cdb::{CDB, Format};
+use libfuzzer_sys::fuzz_target;
+
+// The first byte is the length of the key to look up, which is
+// followed by the key and then the file.
+fuzz_target!(|data: &[u8]| {
+    let Some((&len, rest)) = data.split_first() else {
+        return;
+    };
+    let (key, file) = rest.split_at((len as usize).min(rest.len()));
+    for format in [Format::Cdb32, Format::Cdb64] {
+        let Ok(cdb) = CDB::with_format(file, format) else {
+            continue;
+        };
+        for result in cdb.find(key) {
+            let _ = result;
+        }
+        for result in cdb.find_ref(key) {
+            let _ = result;
+        }
+        for offset in cdb.find_offsets(key).flatten() {
+            let _ = cdb.record_at(offset);
+        }
+        let _ = cdb.count(key);
+        let _ = cdb.get_many(&[key, &b""[..], file]);
+        if let Some(Ok(mut reader)) = cdb.get_reader(key) {
+            let _ = std::io::copy(&mut reader, &mut std::io::sink());
+        }
+    }
+});
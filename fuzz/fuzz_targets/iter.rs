@@ -0,0 +1,26 @@
+#![no_main]
+
+use cdb::{CDB, Cursor, Format};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    for format in [Format::Cdb32, Format::Cdb64] {
+        let Ok(cdb) = CDB::with_format(data, format) else {
+            continue;
+        };
+        let mut iter = cdb.iter();
+        for result in iter.by_ref() {
+            let _ = result;
+        }
+        let _ = cdb.iter_from(iter.cursor());
+        for result in cdb.iter_ref() {
+            let _ = result;
+        }
+        for offset in cdb.iter_offsets().flatten() {
+            let _ = cdb.record_ref_at(offset);
+            if let Ok(iter) = cdb.iter_ref_from(Cursor::new(offset)) {
+                let _ = iter.take(2).count();
+            }
+        }
+    }
+});
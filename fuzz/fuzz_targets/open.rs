@@ -0,0 +1,15 @@
+#![no_main]
+
+use cdb::{CDB, Format};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    for format in [Format::Cdb32, Format::Cdb64] {
+        if let Ok(cdb) = CDB::with_format(data, format) {
+            let _ = cdb.len();
+            let _ = cdb.stats();
+            let _ = cdb.verify();
+        }
+    }
+    let _ = CDB::detect(data);
+});
@@ -0,0 +1,204 @@
+use futures_core::Stream;
+use std::future::Future;
+use std::io;
+use std::path;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::task::{JoinError, JoinHandle, spawn_blocking};
+
+use crate::error::Error;
+use crate::hash::{CDBHash, DjbHash};
+use crate::reader::{CDB, Lookup, Result, Scan};
+use crate::storage::{PreadFile, Storage};
+
+fn err_join(err: JoinError) -> Error {
+    Error::Io(io::Error::other(err))
+}
+
+/// Asynchronous CDB file reader.
+///
+/// All reads from the underlying storage are done on tokio's blocking
+/// thread pool, so lookups never stall the executor on disk I/O. By
+/// default, the file is read with positional reads rather than a memory
+/// map, as page faults on a map would still block the calling thread.
+///
+/// This type is only available with the `tokio` feature.
+///
+/// # Example
+///
+/// ```
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let cdb = cdb::AsyncCDB::open("tests/test1.cdb").await.unwrap();
+/// let value = cdb.get(b"two").await.unwrap().unwrap();
+/// assert_eq!(value, b"Goodbye");
+/// # });
+/// ```
+pub struct AsyncCDB<S = PreadFile, H = DjbHash> {
+    cdb: Arc<CDB<S, H>>,
+}
+
+impl<S, H> Clone for AsyncCDB<S, H> {
+    fn clone(&self) -> Self {
+        AsyncCDB {
+            cdb: Arc::clone(&self.cdb),
+        }
+    }
+}
+
+impl AsyncCDB {
+    /// Opens the named file for reading with positional reads.
+    pub async fn open<P: AsRef<path::Path>>(filename: P) -> Result<AsyncCDB> {
+        let filename = filename.as_ref().to_path_buf();
+        let cdb = spawn_blocking(move || CDB::open_pread(filename))
+            .await
+            .map_err(err_join)??;
+        Ok(AsyncCDB::new(cdb))
+    }
+}
+
+impl<S, H> AsyncCDB<S, H>
+where
+    S: Storage + Send + Sync + 'static,
+    H: CDBHash + Send + Sync + 'static,
+{
+    /// Wraps an already opened CDB reader.
+    pub fn new(cdb: CDB<S, H>) -> AsyncCDB<S, H> {
+        AsyncCDB { cdb: Arc::new(cdb) }
+    }
+
+    /// Find the first record with the named key.
+    pub async fn get(&self, key: &[u8]) -> Option<Result<Vec<u8>>> {
+        let cdb = Arc::clone(&self.cdb);
+        let key = key.to_vec();
+        match spawn_blocking(move || cdb.get(&key)).await {
+            Ok(result) => result,
+            Err(err) => Some(Err(err_join(err))),
+        }
+    }
+
+    /// Find all records with the named key. The returned stream
+    /// produces each value associated with the key.
+    pub fn find(&self, key: &[u8]) -> AsyncValueStream<S, H> {
+        AsyncValueStream {
+            inner: Blocking::new(
+                &self.cdb,
+                (key.to_vec(), Lookup::new(self.cdb.hasher().hash(key))),
+                |cdb, (key, lookup)| {
+                    let (dpos, dlen) = match lookup.next(cdb, key)? {
+                        Ok(found) => found,
+                        Err(err) => return Some(Err(err)),
+                    };
+                    Some(cdb.read_vec(dpos, dlen))
+                },
+            ),
+        }
+    }
+
+    /// Stream all the `(key, value)` pairs in the database.
+    pub fn iter(&self) -> AsyncKeyValueStream<S, H> {
+        AsyncKeyValueStream {
+            inner: Blocking::new(&self.cdb, Scan::new(), |cdb, scan| {
+                let (kpos, klen, dlen) = match scan.next(cdb)? {
+                    Ok(found) => found,
+                    Err(err) => return Some(Err(err)),
+                };
+                let key = cdb.read_vec(kpos, klen);
+                let value = cdb.read_vec(kpos + klen, dlen);
+                Some(key.and_then(|key| value.map(|value| (key, value))))
+            }),
+        }
+    }
+}
+
+type Step<S, H, St, T> = fn(&CDB<S, H>, &mut St) -> Option<Result<T>>;
+
+/// Drives an iteration state one step at a time on the blocking pool.
+struct Blocking<S, H, St, T> {
+    cdb: Arc<CDB<S, H>>,
+    state: Option<St>,
+    step: Step<S, H, St, T>,
+    pending: Option<JoinHandle<(St, Option<Result<T>>)>>,
+}
+
+impl<S, H, St, T> Blocking<S, H, St, T>
+where
+    S: Storage + Send + Sync + 'static,
+    H: CDBHash + Send + Sync + 'static,
+    St: Send + 'static,
+    T: Send + 'static,
+{
+    fn new(cdb: &Arc<CDB<S, H>>, state: St, step: Step<S, H, St, T>) -> Self {
+        Blocking {
+            cdb: Arc::clone(cdb),
+            state: Some(state),
+            step,
+            pending: None,
+        }
+    }
+
+    fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<T>>> {
+        if self.pending.is_none() {
+            let mut state = match self.state.take() {
+                Some(state) => state,
+                None => return Poll::Ready(None),
+            };
+            let cdb = Arc::clone(&self.cdb);
+            let step = self.step;
+            self.pending = Some(spawn_blocking(move || {
+                let item = step(&cdb, &mut state);
+                (state, item)
+            }));
+        }
+        // The unwrap() is safe here, as pending was filled in above.
+        let result = match Pin::new(self.pending.as_mut().unwrap()).poll(cx) {
+            Poll::Pending => return Poll::Pending,
+            Poll::Ready(result) => result,
+        };
+        self.pending = None;
+        Poll::Ready(match result {
+            Ok((_, None)) => None,
+            Ok((state, item)) => {
+                self.state = Some(state);
+                item
+            }
+            Err(err) => Some(Err(err_join(err))),
+        })
+    }
+}
+
+/// Stream over a set of records in the CDB with the same key.
+///
+/// See [`AsyncCDB::find`](struct.AsyncCDB.html#method.find)
+pub struct AsyncValueStream<S = PreadFile, H = DjbHash> {
+    inner: Blocking<S, H, (Vec<u8>, Lookup), Vec<u8>>,
+}
+
+impl<S, H> Stream for AsyncValueStream<S, H>
+where
+    S: Storage + Send + Sync + 'static,
+    H: CDBHash + Send + Sync + 'static,
+{
+    type Item = Result<Vec<u8>>;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().inner.poll_next(cx)
+    }
+}
+
+/// Stream over all the records in the CDB.
+///
+/// See [`AsyncCDB::iter`](struct.AsyncCDB.html#method.iter)
+pub struct AsyncKeyValueStream<S = PreadFile, H = DjbHash> {
+    inner: Blocking<S, H, Scan, (Vec<u8>, Vec<u8>)>,
+}
+
+impl<S, H> Stream for AsyncKeyValueStream<S, H>
+where
+    S: Storage + Send + Sync + 'static,
+    H: CDBHash + Send + Sync + 'static,
+{
+    type Item = Result<(Vec<u8>, Vec<u8>)>;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().inner.poll_next(cx)
+    }
+}
@@ -0,0 +1,240 @@
+use std::fs::Permissions;
+use std::io;
+use std::path;
+use std::string;
+use tokio::fs;
+use tokio::io::{AsyncSeekExt, AsyncWriteExt, BufWriter};
+
+use crate::format::Format;
+use crate::hash::{CDBHash, DjbHash};
+use crate::writer::{Index, Result};
+
+/// Asynchronous interface for making a CDB file.
+///
+/// The output is byte-for-byte identical to that of
+/// [`CDBMake`](struct.CDBMake.html) given the same records.
+///
+/// This type is only available with the `tokio` feature.
+///
+/// # Example
+///
+/// ```no_run
+/// # async fn example() -> std::io::Result<()> {
+/// let file = tokio::fs::File::create("temporary.cdb").await?;
+/// let mut cdb = cdb::AsyncCDBMake::new(file).await?;
+/// cdb.add(b"one", b"Hello,").await?;
+/// cdb.add(b"two", b"world!").await?;
+/// cdb.finish().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct AsyncCDBMake<H = DjbHash> {
+    index: Index<H>,
+    file: BufWriter<fs::File>,
+}
+
+impl AsyncCDBMake {
+    /// Create a new CDB maker.
+    pub async fn new(file: fs::File) -> Result<AsyncCDBMake> {
+        AsyncCDBMake::with_format(file, Format::Cdb32).await
+    }
+
+    /// Create a new CDB maker, writing a CDB in the given format.
+    pub async fn with_format(file: fs::File, format: Format) -> Result<AsyncCDBMake> {
+        AsyncCDBMake::with_hasher(file, format, DjbHash).await
+    }
+}
+
+impl<H: CDBHash> AsyncCDBMake<H> {
+    /// Create a new CDB maker, writing a CDB in the given format with
+    /// keys hashed by the given hash function.
+    pub async fn with_hasher(file: fs::File, format: Format, hasher: H) -> Result<AsyncCDBMake<H>> {
+        let mut w = BufWriter::new(file);
+        let index = Index::new(format, hasher);
+        let buf = vec![0; index.header_size()];
+        w.seek(io::SeekFrom::Start(0)).await?;
+        w.write_all(&buf).await?;
+        Ok(AsyncCDBMake { index, file: w })
+    }
+
+    /// Add a record to the CDB file.
+    pub async fn add(&mut self, key: &[u8], data: &[u8]) -> Result<()> {
+        let buf = self.index.add(key, data)?;
+        self.file.write_all(buf).await?;
+        self.file.write_all(key).await?;
+        self.file.write_all(data).await?;
+        Ok(())
+    }
+
+    /// Set the permissions on the underlying file.
+    pub async fn set_permissions(&self, perm: Permissions) -> Result<()> {
+        Ok(self.file.get_ref().set_permissions(perm).await?)
+    }
+
+    /// Finish writing to the CDB file and flush its contents,
+    /// returning the underlying file positioned at the end of the CDB.
+    pub async fn finish(mut self) -> Result<fs::File> {
+        let mut table = self.index.start_tables()?;
+        let mut header = vec![0_u8; self.index.header_size()];
+        let mut buf = Vec::new();
+        for i in 0..256 {
+            self.index
+                .build_table(i, &mut table, &mut header, &mut buf)?;
+            self.file.write_all(&buf).await?;
+        }
+        if let Some(trailer) = self.index.trailer() {
+            self.file.write_all(&trailer).await?;
+        }
+
+        self.file.flush().await?;
+        self.file.seek(io::SeekFrom::Start(0)).await?;
+        self.file.write_all(&header).await?;
+        self.file.seek(io::SeekFrom::End(0)).await?;
+        self.file.flush().await?;
+        Ok(self.file.into_inner())
+    }
+}
+
+/// An asynchronous CDB file writer which handles atomic updating.
+///
+/// This is the asynchronous counterpart of
+/// [`CDBWriter`](struct.CDBWriter.html). The CDB is built in a
+/// temporary file which is renamed over the final file name when
+/// finished. If the writer is dropped before it is finished, the
+/// temporary file is deleted.
+///
+/// This type is only available with the `tokio` feature.
+///
+/// # Example
+///
+/// ```no_run
+/// # async fn example() -> std::io::Result<()> {
+/// let mut cdb = cdb::AsyncCDBWriter::create("temporary.cdb").await?;
+/// cdb.add(b"one", b"Hello").await?;
+/// cdb.finish().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct AsyncCDBWriter<H = DjbHash> {
+    dstname: String,
+    tmpname: String,
+    cdb: Option<AsyncCDBMake<H>>,
+}
+
+impl AsyncCDBWriter {
+    /// Safely create a new CDB file.
+    ///
+    /// The suffix for the temporary file defaults to `".tmp"`.
+    pub async fn create<P: AsRef<path::Path> + string::ToString>(
+        filename: P,
+    ) -> Result<AsyncCDBWriter> {
+        AsyncCDBWriter::with_suffix(filename, ".tmp").await
+    }
+
+    /// Safely create a new CDB file in the given format.
+    ///
+    /// The suffix for the temporary file defaults to `".tmp"`.
+    pub async fn with_format<P: AsRef<path::Path> + string::ToString>(
+        filename: P,
+        format: Format,
+    ) -> Result<AsyncCDBWriter> {
+        AsyncCDBWriter::with_hasher(filename, format, DjbHash).await
+    }
+
+    /// Safely create a new CDB file, using a specific suffix for the temporary file.
+    pub async fn with_suffix<P: AsRef<path::Path> + string::ToString>(
+        filename: P,
+        suffix: &str,
+    ) -> Result<AsyncCDBWriter> {
+        let mut tmpname = filename.to_string();
+        tmpname.push_str(suffix);
+        AsyncCDBWriter::with_filenames(filename, &tmpname).await
+    }
+
+    /// Safely create a new CDB file, using two specific file names.
+    ///
+    /// Note that the temporary file name must be on the same filesystem
+    /// as the destination, or else the final rename will fail.
+    pub async fn with_filenames<
+        P: AsRef<path::Path> + string::ToString,
+        Q: AsRef<path::Path> + string::ToString,
+    >(
+        filename: P,
+        tmpname: Q,
+    ) -> Result<AsyncCDBWriter> {
+        AsyncCDBWriter::with_filenames_and_hasher(filename, tmpname, Format::Cdb32, DjbHash).await
+    }
+}
+
+impl<H: CDBHash> AsyncCDBWriter<H> {
+    /// Safely create a new CDB file in the given format, with keys
+    /// hashed by the given hash function.
+    ///
+    /// The suffix for the temporary file defaults to `".tmp"`.
+    pub async fn with_hasher<P: AsRef<path::Path> + string::ToString>(
+        filename: P,
+        format: Format,
+        hasher: H,
+    ) -> Result<AsyncCDBWriter<H>> {
+        let mut tmpname = filename.to_string();
+        tmpname.push_str(".tmp");
+        AsyncCDBWriter::with_filenames_and_hasher(filename, tmpname, format, hasher).await
+    }
+
+    /// Safely create a new CDB file in the given format, with keys
+    /// hashed by the given hash function, using two specific file
+    /// names.
+    ///
+    /// Note that the temporary file name must be on the same filesystem
+    /// as the destination, or else the final rename will fail.
+    pub async fn with_filenames_and_hasher<
+        P: AsRef<path::Path> + string::ToString,
+        Q: AsRef<path::Path> + string::ToString,
+    >(
+        filename: P,
+        tmpname: Q,
+        format: Format,
+        hasher: H,
+    ) -> Result<AsyncCDBWriter<H>> {
+        let file = fs::File::create(&tmpname).await?;
+        let cdb = AsyncCDBMake::with_hasher(file, format, hasher).await?;
+        Ok(AsyncCDBWriter {
+            dstname: filename.to_string(),
+            tmpname: tmpname.to_string(),
+            cdb: Some(cdb),
+        })
+    }
+
+    /// Add a record to the CDB file.
+    pub async fn add(&mut self, key: &[u8], data: &[u8]) -> Result<()> {
+        // The unwrap() is safe here, as the internal cdb is only ever
+        // None during finish(), which does not call this.
+        self.cdb.as_mut().unwrap().add(key, data).await
+    }
+
+    /// Set permissions on the temporary file.
+    ///
+    /// This must be done before the file is finished, as the temporary
+    /// file will no longer exist at that point.
+    pub async fn set_permissions(&self, perm: Permissions) -> Result<()> {
+        self.cdb.as_ref().unwrap().set_permissions(perm).await
+    }
+
+    /// Finish writing the temporary file and rename it over the final
+    /// file name.
+    pub async fn finish(mut self) -> Result<()> {
+        self.cdb.take().unwrap().finish().await?;
+        fs::rename(&self.tmpname, &self.dstname).await?;
+        Ok(())
+    }
+}
+
+impl<H> Drop for AsyncCDBWriter<H> {
+    #[allow(unused_must_use)]
+    fn drop(&mut self) {
+        // Drop cannot wait, so the temporary file is removed synchronously.
+        if self.cdb.is_some() {
+            std::fs::remove_file(&self.tmpname);
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use alloc::vec::Vec;
+
+use crate::hash::CDBHash;
+use crate::reader::{CDB, Lookup, Result};
+use crate::storage::Storage;
+
+/// The progress of one lookup in a batch.
+struct Pending {
+    index: usize,
+    hash: u32,
+    /// The position and number of slots of the key's hash table.
+    hpos: u64,
+    hslots: u64,
+    /// The position to be read next, used to order the reads.
+    pos: u64,
+    len: u64,
+    /// The search to continue if the first candidate does not match.
+    lookup: Lookup,
+    done: bool,
+}
+
+impl<S: Storage, H: CDBHash> CDB<S, H> {
+    /// Find the first record for each of a batch of keys.
+    ///
+    /// The results are in the same order as the keys, and are the same
+    /// as calling [`get`](#method.get) for each key. The lookups are
+    /// done in stages, first hinting to the storage which hash table
+    /// slots, records and values all of the keys will touch, and then
+    /// reading each in file order. When the file is not already in
+    /// memory, this lets the reads for all the keys proceed together
+    /// instead of waiting on each page in turn.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "std")] {
+    /// let cdb = cdb::CDB::open("tests/test1.cdb").unwrap();
+    /// let values = cdb.get_many(&["one", "two", "four"]);
+    /// assert_eq!(values[0].as_ref().unwrap().as_ref().unwrap(), b"Hello");
+    /// assert_eq!(values[1].as_ref().unwrap().as_ref().unwrap(), b"Goodbye");
+    /// assert!(values[2].is_none());
+    /// # }
+    /// ```
+    pub fn get_many<K: AsRef<[u8]>>(&self, keys: &[K]) -> Vec<Option<Result<Vec<u8>>>> {
+        let mut results: Vec<Option<Result<Vec<u8>>>> = (0..keys.len()).map(|_| None).collect();
+        let pair_size = self.format().pair_size();
+        let storage = self.storage();
+        storage.prefetch(0, self.format().header_size());
+
+        // Find the first slot to probe for each key.
+        let mut pending: Vec<Pending> = keys
+            .iter()
+            .enumerate()
+            .map(|(index, key)| Pending {
+                index,
+                hash: self.hasher().hash(key.as_ref()),
+                hpos: 0,
+                hslots: 0,
+                pos: 0,
+                len: 0,
+                lookup: Lookup::new(0),
+                done: false,
+            })
+            .collect();
+        for p in &mut pending {
+            match self.hash_table(p.hash) {
+                Ok((hpos, hslots, kpos)) => {
+                    p.hpos = hpos;
+                    p.hslots = hslots;
+                    p.pos = kpos;
+                    p.done = hslots == 0;
+                    storage.prefetch(kpos, pair_size);
+                }
+                Err(err) => {
+                    results[p.index] = Some(Err(err));
+                    p.done = true;
+                }
+            }
+        }
+        pending.retain(|p| !p.done);
+
+        // Probe the slots for the first record with a matching hash.
+        pending.sort_unstable_by_key(|p| p.pos);
+        for p in &mut pending {
+            match self.first_candidate(p) {
+                Ok(Some((pos, lookup))) => {
+                    p.pos = pos;
+                    p.lookup = lookup;
+                    storage.prefetch(pos, pair_size + keys[p.index].as_ref().len() as u64);
+                }
+                Ok(None) => p.done = true,
+                Err(err) => {
+                    results[p.index] = Some(Err(err));
+                    p.done = true;
+                }
+            }
+        }
+        pending.retain(|p| !p.done);
+
+        // Match the keys to find the values.
+        pending.sort_unstable_by_key(|p| p.pos);
+        for p in &mut pending {
+            let key = keys[p.index].as_ref();
+            let found = match self.match_record(key, p.pos) {
+                Ok(Some(found)) => Some(Ok(found)),
+                Ok(None) => p.lookup.next(self, key),
+                Err(err) => Some(Err(err)),
+            };
+            match found {
+                Some(Ok((dpos, dlen))) => {
+                    p.pos = dpos;
+                    p.len = dlen;
+                    storage.prefetch(dpos, dlen);
+                }
+                Some(Err(err)) => {
+                    results[p.index] = Some(Err(err));
+                    p.done = true;
+                }
+                None => p.done = true,
+            }
+        }
+        pending.retain(|p| !p.done);
+
+        // Finally read the values.
+        pending.sort_unstable_by_key(|p| p.pos);
+        for p in &pending {
+            results[p.index] = Some(self.read_vec(p.pos, p.len));
+        }
+        results
+    }
+
+    /// Find the position of the first record whose slot matches the
+    /// hash, starting from the given slot, along with the search to
+    /// continue from the slot after it.
+    fn first_candidate(&self, p: &Pending) -> Result<Option<(u64, Lookup)>> {
+        let pair_size = self.format().pair_size();
+        let (hpos, hslots) = (p.hpos, p.hslots);
+        let mut kpos = p.pos;
+        for kloop in 1..=hslots {
+            let (khash, pos) = self.read_pair(kpos)?;
+            if pos == 0 {
+                return Ok(None);
+            }
+            kpos += pair_size;
+            if kpos == hpos + hslots * pair_size {
+                kpos = hpos;
+            }
+            if khash == p.hash as u64 {
+                let lookup = Lookup::resume(p.hash, hpos, hslots, kpos, kloop);
+                return Ok(Some((pos, lookup)));
+            }
+        }
+        Ok(None)
+    }
+}
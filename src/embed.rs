@@ -0,0 +1,166 @@
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, Read};
+use std::path;
+
+use crate::error::Error;
+use crate::reader::Result;
+use crate::writer::CDBWriter;
+
+/// Build a CDB in the `OUT_DIR` of a build script from a file in the
+/// input format of `cdbmake`, returning the path of the CDB.
+///
+/// The CDB is named `name.cdb`, and can be embedded in the crate with
+/// [`include_cdb!`](macro.include_cdb.html). The build script is also
+/// set to be rerun whenever the source file changes.
+///
+/// Each record in the source file is written as `+klen,dlen:key->data`
+/// followed by a newline, where `klen` and `dlen` are the lengths of the
+/// key and data in bytes, and the end of the records is marked by an
+/// empty line.
+///
+/// This function is only available with the `std` feature.
+///
+/// # Example
+///
+/// In the `main` function of `build.rs`:
+///
+/// ```no_run
+/// cdb::compile("data/colors.txt", "colors").unwrap();
+/// ```
+///
+/// And in the crate:
+///
+/// ```ignore
+/// static COLORS: cdb::CDB<&[u8]> = cdb::include_cdb!("colors");
+///
+/// fn color(name: &str) -> Option<&'static [u8]> {
+///     COLORS.get_ref(name.as_bytes()).and_then(Result::ok)
+/// }
+/// ```
+pub fn compile<P: AsRef<path::Path>>(source: P, name: &str) -> Result<path::PathBuf> {
+    let out_dir = env::var_os("OUT_DIR")
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "OUT_DIR is not set"))?;
+    let dest = path::PathBuf::from(out_dir).join(format!("{}.cdb", name));
+    compile_to(&source, &dest)?;
+    println!("cargo:rerun-if-changed={}", source.as_ref().display());
+    Ok(dest)
+}
+
+/// Build a CDB at the given path from a file in the input format of
+/// `cdbmake`, returning the number of records.
+///
+/// The CDB is written with [`CDBWriter`](struct.CDBWriter.html), so the
+/// destination path must be valid UTF-8.
+///
+/// See [`compile`](fn.compile.html)
+///
+/// This function is only available with the `std` feature.
+pub fn compile_to<P, Q>(source: P, dest: Q) -> Result<u64>
+where
+    P: AsRef<path::Path>,
+    Q: AsRef<path::Path>,
+{
+    let dest = dest.as_ref().to_str().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "destination path is not valid UTF-8",
+        )
+    })?;
+    let mut input = Input {
+        inner: io::BufReader::new(fs::File::open(source)?),
+        offset: 0,
+    };
+    let mut cdb = CDBWriter::create(dest)?;
+    let mut records = 0;
+    let (mut key, mut data) = (Vec::new(), Vec::new());
+    loop {
+        match input.byte()? {
+            b'+' => (),
+            b'\n' => break,
+            _ => return input.unexpected(),
+        }
+        let klen = input.number(b',')?;
+        let dlen = input.number(b':')?;
+        input.bytes(klen, &mut key)?;
+        input.expect(b"->")?;
+        input.bytes(dlen, &mut data)?;
+        input.expect(b"\n")?;
+        cdb.add(&key, &data)?;
+        records += 1;
+    }
+    cdb.finish()?;
+    Ok(records)
+}
+
+/// A reader of cdbmake input, which tracks the offset for errors.
+struct Input<R> {
+    inner: R,
+    offset: u64,
+}
+
+impl<R: BufRead> Input<R> {
+    /// Report the byte just read as unexpected.
+    fn unexpected<T>(&self) -> Result<T> {
+        Err(Error::Syntax {
+            offset: self.offset - 1,
+        })
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        let mut byte = [0];
+        match self.inner.read_exact(&mut byte) {
+            Ok(()) => {
+                self.offset += 1;
+                Ok(byte[0])
+            }
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => Err(Error::Syntax {
+                offset: self.offset,
+            }),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn expect(&mut self, expected: &[u8]) -> Result<()> {
+        for &expected in expected {
+            if self.byte()? != expected {
+                return self.unexpected();
+            }
+        }
+        Ok(())
+    }
+
+    /// Read a decimal number ending with the given byte.
+    fn number(&mut self, end: u8) -> Result<u64> {
+        let mut n: u64 = 0;
+        let mut digits = 0;
+        loop {
+            match self.byte()? {
+                byte @ b'0'..=b'9' => {
+                    let digit = (byte - b'0') as u64;
+                    n = match n.checked_mul(10).and_then(|n| n.checked_add(digit)) {
+                        Some(n) => n,
+                        None => return self.unexpected(),
+                    };
+                    digits += 1;
+                }
+                byte if byte == end && digits > 0 => return Ok(n),
+                _ => return self.unexpected(),
+            }
+        }
+    }
+
+    /// Read the given number of bytes, without trusting the length to
+    /// allocate ahead of time.
+    fn bytes(&mut self, len: u64, buf: &mut Vec<u8>) -> Result<()> {
+        buf.clear();
+        let n = (&mut self.inner).take(len).read_to_end(buf)? as u64;
+        self.offset += n;
+        if n < len {
+            return Err(Error::Syntax {
+                offset: self.offset,
+            });
+        }
+        Ok(())
+    }
+}
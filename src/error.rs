@@ -0,0 +1,133 @@
+use core::error;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io;
+
+/// The error type for reading and writing CDB files.
+///
+/// Errors are either I/O errors from the underlying file or storage,
+/// which may succeed if retried, or describe a problem with the
+/// contents of the file, which will not. Use
+/// [`is_corrupt`](#method.is_corrupt) to tell these apart.
+///
+/// With the `std` feature, every error can be converted into an
+/// `io::Error`, so the `?` operator continues to work in functions
+/// returning `io::Result`. The [`Io`](#variant.Io) variant only exists
+/// with the `std` feature; as the enum is non-exhaustive, matching on
+/// it already requires a wildcard arm, so enabling `std` breaks no
+/// code written without it.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// An I/O error from the underlying file or storage.
+    #[cfg(feature = "std")]
+    Io(io::Error),
+    /// The file is shorter than its contents say it should be.
+    Truncated,
+    /// A position or length in the file points outside of the file or
+    /// the region it should point into. The offset is the position
+    /// being pointed at.
+    OutOfRange { offset: u64 },
+    /// The record at the given offset extends past the end of the
+    /// record region.
+    RecordPastEnd { offset: u64 },
+    /// The file, or the database being written, is larger than its
+    /// format can address.
+    SizeLimit,
+    /// A key or value is too big to be written in the format.
+    TooBig,
+    /// The format of the file could not be determined.
+    UnknownFormat,
+    /// The file was written with a different hash function.
+    HashMismatch,
+    /// A cursor does not point at a record. The offset is that of the
+    /// cursor.
+    InvalidCursor { offset: u64 },
+    /// Input in the format of `cdbmake` is malformed at the given
+    /// offset.
+    Syntax { offset: u64 },
+}
+
+/// A specialized `Result` type for CDB operations.
+pub type Result<T> = core::result::Result<T, Error>;
+
+impl Error {
+    /// Returns true if the error is due to the contents of the file
+    /// being invalid, meaning the file needs to be rebuilt.
+    pub fn is_corrupt(&self) -> bool {
+        matches!(
+            self,
+            Error::Truncated
+                | Error::OutOfRange { .. }
+                | Error::RecordPastEnd { .. }
+                | Error::UnknownFormat
+        )
+    }
+
+    /// The `io::ErrorKind` corresponding to this error.
+    #[cfg(feature = "std")]
+    pub fn kind(&self) -> io::ErrorKind {
+        match self {
+            Error::Io(err) => err.kind(),
+            Error::Truncated => io::ErrorKind::UnexpectedEof,
+            Error::OutOfRange { .. }
+            | Error::RecordPastEnd { .. }
+            | Error::UnknownFormat
+            | Error::HashMismatch
+            | Error::Syntax { .. } => io::ErrorKind::InvalidData,
+            Error::SizeLimit => io::ErrorKind::FileTooLarge,
+            Error::TooBig | Error::InvalidCursor { .. } => io::ErrorKind::InvalidInput,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            Error::Io(err) => err.fmt(f),
+            Error::Truncated => write!(f, "Invalid file format: truncated file"),
+            Error::OutOfRange { offset } => {
+                write!(f, "Invalid file format: position {} out of range", offset)
+            }
+            Error::RecordPastEnd { offset } => write!(
+                f,
+                "Invalid file format: record at {} extends past the end of the records",
+                offset
+            ),
+            Error::SizeLimit => write!(f, "File too big"),
+            Error::TooBig => write!(f, "Key or data too big"),
+            Error::UnknownFormat => write!(f, "Invalid file format"),
+            Error::HashMismatch => write!(f, "Hash function mismatch"),
+            Error::InvalidCursor { offset } => write!(f, "Invalid cursor at {}", offset),
+            Error::Syntax { offset } => write!(f, "Invalid input at byte {}", offset),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "std")]
+            Error::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<Error> for io::Error {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::Io(err) => err,
+            err => io::Error::new(err.kind(), err),
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use alloc::vec;
+#[cfg(feature = "std")]
+use std::path;
+#[cfg(feature = "std")]
+use std::string;
+
+use crate::error::Error;
+#[cfg(feature = "std")]
+use crate::reader::CDB;
+use crate::storage::Storage;
+#[cfg(feature = "std")]
+use crate::writer::CDBWriter;
+use crate::{uint32, uint64};
+
+pub use crate::error::Result;
+
+/// The layout of a CDB file.
+///
+/// Every position and length in a CDB file is stored as a pair of
+/// little-endian numbers: the header entries pointing at the hash
+/// tables, the hash table slots, and the key and value lengths at the
+/// start of each record. The formats differ only in the width of
+/// those numbers. Keys are hashed the same way in both formats.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Format {
+    /// The classic format, using 32-bit numbers. Files are limited to
+    /// 4 GiB.
+    Cdb32,
+    /// The "cdb64" format, using 64-bit numbers, as used by
+    /// `python-pure-cdb`'s `Reader64` and `Writer64` among others. The
+    /// header at the start of the file is 4096 bytes instead of 2048.
+    Cdb64,
+}
+
+impl Format {
+    /// The size of a pair of numbers in this format.
+    pub(crate) fn pair_size(self) -> u64 {
+        match self {
+            Format::Cdb32 => 8,
+            Format::Cdb64 => 16,
+        }
+    }
+
+    /// The size of the header at the start of the file.
+    pub(crate) fn header_size(self) -> u64 {
+        256 * self.pair_size()
+    }
+
+    /// The largest position or length that can be stored.
+    pub(crate) fn max_pos(self) -> u64 {
+        match self {
+            Format::Cdb32 => 0xffffffff,
+            Format::Cdb64 => u64::MAX,
+        }
+    }
+
+    pub(crate) fn unpack2(self, buf: &[u8]) -> (u64, u64) {
+        match self {
+            Format::Cdb32 => {
+                let (a, b) = uint32::unpack2(buf);
+                (a as u64, b as u64)
+            }
+            Format::Cdb64 => uint64::unpack2(buf),
+        }
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn pack2(self, buf: &mut [u8], a: u64, b: u64) {
+        match self {
+            Format::Cdb32 => uint32::pack2(buf, a as u32, b as u32),
+            Format::Cdb64 => uint64::pack2(buf, a, b),
+        }
+    }
+
+    /// Determine the format of the CDB in the given storage.
+    ///
+    /// A header is accepted when its hash tables lie one after the
+    /// other, in order, between the end of the header and the end of
+    /// the storage, as every CDB writer lays them out. The classic
+    /// format is tried first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let data = std::fs::read("tests/test1.cdb").unwrap();
+    /// assert_eq!(cdb::Format::detect(&data).unwrap(), cdb::Format::Cdb32);
+    /// ```
+    pub fn detect<S: Storage>(storage: &S) -> Result<Format> {
+        for format in [Format::Cdb32, Format::Cdb64] {
+            if format.check_header(storage)? {
+                return Ok(format);
+            }
+        }
+        Err(Error::UnknownFormat)
+    }
+
+    fn check_header<S: Storage>(self, storage: &S) -> Result<bool> {
+        let size = storage.size();
+        let header_size = self.header_size();
+        if size < header_size || size > self.max_pos() {
+            return Ok(false);
+        }
+        let mut header = vec![0; header_size as usize];
+        storage.read_at(&mut header, 0)?;
+        let pair_size = self.pair_size();
+        let mut next = None;
+        for entry in header.chunks(pair_size as usize) {
+            let (pos, slots) = self.unpack2(entry);
+            let end = slots
+                .checked_mul(pair_size)
+                .and_then(|len| len.checked_add(pos));
+            match end {
+                Some(end) if pos >= header_size && end <= size => {
+                    if next.is_some_and(|next| next != pos) {
+                        return Ok(false);
+                    }
+                    next = Some(end);
+                }
+                _ => return Ok(false),
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Convert a CDB file between formats.
+///
+/// The source file may be in either format, and is detected
+/// automatically. The destination is written atomically in the given
+/// format using a [`CDBWriter`](struct.CDBWriter.html), with the records
+/// in the same order as the source.
+///
+/// # Example
+///
+/// ```no_run
+/// fn main() -> cdb::Result<()> {
+///     cdb::convert("small.cdb", "large.cdb", cdb::Format::Cdb64)
+/// }
+/// ```
+///
+/// This function is only available with the `std` feature.
+#[cfg(feature = "std")]
+pub fn convert<P, Q>(srcname: P, dstname: Q, format: Format) -> Result<()>
+where
+    P: AsRef<path::Path>,
+    Q: AsRef<path::Path> + string::ToString,
+{
+    let src = CDB::open_detect(srcname)?;
+    let mut dst = CDBWriter::with_format(dstname, format)?;
+    for record in src.iter_ref() {
+        let (key, value) = record?;
+        dst.add(key, value)?;
+    }
+    dst.finish()
+}
@@ -13,9 +13,180 @@ pub fn hash(buf: &[u8]) -> u32 {
     h
 }
 
+/// A hash function for the keys in a CDB.
+///
+/// The same hash function must be used to read a CDB as was used to
+/// write it. Every hash function other than the classic one identifies
+/// itself with an [`id`](#tymethod.id), which the writer records after
+/// the hash tables and the reader checks when the file is opened.
+pub trait CDBHash {
+    /// Hash a key.
+    fn hash(&self, key: &[u8]) -> u32;
+
+    /// The identifier recorded in files written with this hash
+    /// function, which must include any seed or parameters that change
+    /// the hash values. Only the classic hash function returns `None`,
+    /// so that its files remain readable by every other CDB reader.
+    fn id(&self) -> Option<[u8; 8]>;
+}
+
+/// The classic hash function from D. J. Bernstein's cdb.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DjbHash;
+
+impl CDBHash for DjbHash {
+    fn hash(&self, key: &[u8]) -> u32 {
+        hash(key)
+    }
+
+    fn id(&self) -> Option<[u8; 8]> {
+        None
+    }
+}
+
+/// The seeded xxHash32 hash function.
+///
+/// This hash distributes keys that share long prefixes or suffixes
+/// much more evenly than the classic hash function does.
+///
+/// # Example
+///
+/// ```no_run
+/// # #[cfg(feature = "std")]
+/// use cdb::{CDBWriter, Format, Xxh32Hash};
+///
+/// fn main() -> std::io::Result<()> {
+/// # #[cfg(feature = "std")] {
+///     let hasher = Xxh32Hash::new(0x5eed);
+///     let mut cdb = CDBWriter::with_hasher("temporary.cdb", Format::Cdb32, hasher)?;
+///     cdb.add(b"one", b"Hello")?;
+///     cdb.finish()?;
+/// # }
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Xxh32Hash {
+    seed: u32,
+}
+
+const PRIME32_1: u32 = 0x9e3779b1;
+const PRIME32_2: u32 = 0x85ebca77;
+const PRIME32_3: u32 = 0xc2b2ae3d;
+const PRIME32_4: u32 = 0x27d4eb2f;
+const PRIME32_5: u32 = 0x165667b1;
+
+fn xxh32_round(acc: u32, input: &[u8]) -> u32 {
+    acc.wrapping_add(read32(input).wrapping_mul(PRIME32_2))
+        .rotate_left(13)
+        .wrapping_mul(PRIME32_1)
+}
+
+fn read32(input: &[u8]) -> u32 {
+    u32::from_le_bytes([input[0], input[1], input[2], input[3]])
+}
+
+impl Xxh32Hash {
+    /// Create the hash function with the given seed.
+    pub fn new(seed: u32) -> Xxh32Hash {
+        Xxh32Hash { seed }
+    }
+
+    /// The seed of the hash function.
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+}
+
+impl CDBHash for Xxh32Hash {
+    fn hash(&self, key: &[u8]) -> u32 {
+        let seed = self.seed;
+        let mut input = key;
+        let mut h = if input.len() >= 16 {
+            let mut v1 = seed.wrapping_add(PRIME32_1).wrapping_add(PRIME32_2);
+            let mut v2 = seed.wrapping_add(PRIME32_2);
+            let mut v3 = seed;
+            let mut v4 = seed.wrapping_sub(PRIME32_1);
+            while input.len() >= 16 {
+                v1 = xxh32_round(v1, &input[0..4]);
+                v2 = xxh32_round(v2, &input[4..8]);
+                v3 = xxh32_round(v3, &input[8..12]);
+                v4 = xxh32_round(v4, &input[12..16]);
+                input = &input[16..];
+            }
+            v1.rotate_left(1)
+                .wrapping_add(v2.rotate_left(7))
+                .wrapping_add(v3.rotate_left(12))
+                .wrapping_add(v4.rotate_left(18))
+        } else {
+            seed.wrapping_add(PRIME32_5)
+        };
+        h = h.wrapping_add(key.len() as u32);
+        while input.len() >= 4 {
+            h = h
+                .wrapping_add(read32(input).wrapping_mul(PRIME32_3))
+                .rotate_left(17)
+                .wrapping_mul(PRIME32_4);
+            input = &input[4..];
+        }
+        for &c in input {
+            h = h
+                .wrapping_add((c as u32).wrapping_mul(PRIME32_5))
+                .rotate_left(11)
+                .wrapping_mul(PRIME32_1);
+        }
+        h ^= h >> 15;
+        h = h.wrapping_mul(PRIME32_2);
+        h ^= h >> 13;
+        h = h.wrapping_mul(PRIME32_3);
+        h ^ (h >> 16)
+    }
+
+    fn id(&self) -> Option<[u8; 8]> {
+        let mut id = *b"xx32\0\0\0\0";
+        id[4..].copy_from_slice(&self.seed.to_le_bytes());
+        Some(id)
+    }
+}
+
+const TRAILER_MAGIC: &[u8; 8] = b"cdb-hash";
+pub(crate) const TRAILER_SIZE: usize = 16;
+
+/// Build the trailer recording the identifier of a hash function.
+#[cfg(feature = "std")]
+pub(crate) fn make_trailer(id: [u8; 8]) -> [u8; TRAILER_SIZE] {
+    let mut trailer = [0; TRAILER_SIZE];
+    trailer[..8].copy_from_slice(TRAILER_MAGIC);
+    trailer[8..].copy_from_slice(&id);
+    trailer
+}
+
+/// Extract the hash function identifier from a trailer, if it is one.
+pub(crate) fn parse_trailer(trailer: &[u8; TRAILER_SIZE]) -> Option<[u8; 8]> {
+    if &trailer[..8] == TRAILER_MAGIC {
+        let mut id = [0; 8];
+        id.copy_from_slice(&trailer[8..]);
+        Some(id)
+    } else {
+        None
+    }
+}
+
 #[test]
 fn samples() {
     assert_eq!(hash(b""), 0x0001505);
     assert_eq!(hash(b"Hello, world!"), 0x564369e8);
     assert_eq!(hash(b"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"), 0x40032705);
 }
+
+#[test]
+fn xxh32_samples() {
+    let h = Xxh32Hash::new(0);
+    assert_eq!(h.hash(b""), 0x02cc5d05);
+    assert_eq!(h.hash(b"abc"), 0x32d153ff);
+    assert_eq!(
+        h.hash(b"Nobody inspects the spammish repetition"),
+        0xe2293b2f
+    );
+    assert_ne!(Xxh32Hash::new(1).hash(b"abc"), h.hash(b"abc"));
+}
@@ -10,38 +10,137 @@
 //! Reading a set of records:
 //!
 //! ```
+//! # #[cfg(feature = "std")] {
 //! let cdb = cdb::CDB::open("tests/test1.cdb").unwrap();
 //!
 //! for result in cdb.find(b"one") {
 //!     println!("{:?}", result.unwrap());
 //! }
+//! # }
 //! ```
 //!
 //! Creating a database with safe atomic updating:
 //!
 //! ```no_run
 //! fn main() -> std::io::Result<()> {
+//! # #[cfg(feature = "std")] {
 //!     let mut cdb = cdb::CDBWriter::create("temporary.cdb")?;
 //!     cdb.add(b"one", b"Hello, ")?;
 //!     cdb.add(b"one", b"world!\n")?;
 //!     cdb.add(b"two", &[1, 2, 3, 4])?;
 //!     cdb.finish()?;
+//! # }
 //!     Ok(())
 //! }
 //! ```
 //!
+//! A database can also be built from a build script with
+//! [`compile`](fn.compile.html) and embedded in the binary with
+//! [`include_cdb!`](macro.include_cdb.html).
+//!
+//! # Untrusted files
+//!
+//! Reading never panics or loops, whatever the contents of the file.
+//! A corrupt file produces errors for which
+//! [`Error::is_corrupt`](enum.Error.html#method.is_corrupt) is true,
+//! and an iterator ends after producing its first error. The `fuzz`
+//! directory holds [cargo-fuzz](https://github.com/rust-fuzz/cargo-fuzz)
+//! targets which check this.
+//!
+//! # Features
+//!
+//!  * `std` (enabled by default): reading files, and everything that
+//!    depends on the standard library. Without it, the crate is
+//!    `no_std` and needs only `alloc`, and the reader works over byte
+//!    slices such as a database embedded with `include_bytes!`:
+//!
+//!    ```
+//!    static DATA: &[u8] = include_bytes!("../tests/test1.cdb");
+//!
+//!    let cdb = cdb::CDB::new(DATA).unwrap();
+//!    assert_eq!(cdb.get_ref(b"two").unwrap().unwrap(), b"Goodbye");
+//!    ```
+//!
+//!    Lookups, iteration, [`stats`](struct.CDB.html#method.stats) and
+//!    [`verify`](struct.CDB.html#method.verify) are available without
+//!    `std`. Writing, file access, memory mapping and the shared and
+//!    reloading readers are not.
+//!  * `rayon`: parallel iteration over all the records with
+//!    [`CDB::par_iter`](struct.CDB.html#method.par_iter).
+//!  * `tokio`: asynchronous reading with [`AsyncCDB`](struct.AsyncCDB.html)
+//!    and writing with [`AsyncCDBWriter`](struct.AsyncCDBWriter.html).
+//!
 //! # References
 //!
 //!  * [D. J. Bernstein's original software](https://cr.yp.to/cdb.html)
 //!  * [Constant Database (cdb) Internals](https://www.unixuser.org/~euske/doc/cdbinternals/index.html)
 //!  * [Wikipedia](https://en.wikipedia.org/wiki/Cdb_(software))
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+#[cfg(feature = "std")]
 extern crate filebuffer;
 
+#[cfg(feature = "tokio")]
+mod async_reader;
+#[cfg(feature = "tokio")]
+mod async_writer;
+mod batch;
+#[cfg(feature = "std")]
+mod embed;
+mod error;
+mod format;
 mod hash;
+#[cfg(all(unix, feature = "std"))]
+mod mmap;
+#[cfg(feature = "rayon")]
+mod parallel;
 mod reader;
+#[cfg(all(unix, feature = "std"))]
+mod reload;
+#[cfg(feature = "std")]
+mod shared;
+mod stats;
+mod storage;
 mod uint32;
+mod uint64;
+#[cfg(feature = "std")]
+mod value;
+mod verify;
+#[cfg(feature = "std")]
 mod writer;
 
-pub use crate::reader::{CDB, CDBIter, CDBKeyValueIter, CDBValueIter, Result};
-pub use crate::writer::{CDBMake, CDBWriter};
+#[cfg(feature = "std")]
+pub use filebuffer::FileBuffer;
+
+#[cfg(feature = "tokio")]
+pub use crate::async_reader::{AsyncCDB, AsyncKeyValueStream, AsyncValueStream};
+#[cfg(feature = "tokio")]
+pub use crate::async_writer::{AsyncCDBMake, AsyncCDBWriter};
+#[cfg(feature = "std")]
+pub use crate::embed::{compile, compile_to};
+pub use crate::error::{Error, Result};
+pub use crate::format::Format;
+#[cfg(feature = "std")]
+pub use crate::format::convert;
+pub use crate::hash::{CDBHash, DjbHash, Xxh32Hash};
+#[cfg(all(unix, feature = "std"))]
+pub use crate::mmap::{Advice, MapOptions, MappedFile, Prefault, Residency};
+pub use crate::reader::{
+    CDB, CDBIter, CDBKeyValueIter, CDBKeyValueRefIter, CDBOffsetIter, CDBValueIter,
+    CDBValueLenIter, CDBValueRefIter, Cursor,
+};
+#[cfg(all(unix, feature = "std"))]
+pub use crate::reload::ReloadingCDB;
+#[cfg(feature = "std")]
+pub use crate::shared::{CDBBytes, SharedCDB, SharedKeyValueIter, SharedValueIter};
+pub use crate::stats::{RecordSize, Stats, TableStats};
+#[cfg(feature = "std")]
+pub use crate::storage::PreadFile;
+pub use crate::storage::{SliceStorage, Storage};
+#[cfg(feature = "std")]
+pub use crate::value::ValueReader;
+pub use crate::verify::{Problem, ProblemKind, Report};
+#[cfg(feature = "std")]
+pub use crate::writer::{CDBBuffer, CDBMake, CDBWriter};
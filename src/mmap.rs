@@ -0,0 +1,344 @@
+use std::fs;
+use std::io;
+use std::os::unix::io::AsRawFd;
+use std::path;
+use std::ptr;
+use std::slice;
+
+use crate::error::Error;
+use crate::format::Format;
+use crate::hash::CDBHash;
+use crate::reader::{CDB, Result, Scan};
+use crate::storage::{SliceStorage, page_range, page_size};
+
+/// Advice to the kernel about how a CDB will be accessed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Advice {
+    /// No particular access pattern, the kernel's default.
+    Normal,
+    /// Random access, as by lookups. Readahead is disabled.
+    Random,
+    /// Sequential access, as by iteration. Readahead is increased.
+    Sequential,
+}
+
+/// Which parts of a CDB to read into memory ahead of time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Prefault {
+    /// The header and the hash tables, which are touched by every
+    /// lookup.
+    HashTables,
+    /// The whole file.
+    All,
+}
+
+/// How much of a CDB is resident in memory.
+///
+/// See [`CDB::residency`](struct.CDB.html#method.residency)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Residency {
+    /// The size of the file in bytes.
+    pub size: u64,
+    /// The number of bytes of the file in resident pages.
+    pub resident: u64,
+}
+
+fn madvise(data: &[u8], pos: u64, len: u64, advice: libc::c_int) -> Result<()> {
+    if let Some((addr, len)) = page_range(data, pos, len) {
+        // SAFETY: the range only covers pages that contain bytes of the
+        // slice, and none of the advice used changes their contents.
+        if unsafe { libc::madvise(addr, len, advice) } != 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+    }
+    Ok(())
+}
+
+/// Read one byte from every page in the range, to fault it in.
+fn touch(data: &[u8], pos: u64, len: u64) {
+    if pos >= data.len() as u64 {
+        return;
+    }
+    let end = pos.saturating_add(len).min(data.len() as u64) as usize;
+    let page = page_size();
+    let mut i = pos as usize;
+    while i < end {
+        // SAFETY: the index is within the slice, and a volatile read
+        // keeps the compiler from discarding it.
+        unsafe { ptr::read_volatile(&data[i]) };
+        i = (i & !(page - 1)) + page;
+    }
+}
+
+impl<S: SliceStorage, H: CDBHash> CDB<S, H> {
+    /// Advise the kernel how the CDB will be accessed.
+    ///
+    /// This is only available on Unix.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let cdb = cdb::CDB::open("tests/test1.cdb").unwrap();
+    /// cdb.advise(cdb::Advice::Random).unwrap();
+    /// ```
+    pub fn advise(&self, advice: Advice) -> Result<()> {
+        let advice = match advice {
+            Advice::Normal => libc::MADV_NORMAL,
+            Advice::Random => libc::MADV_RANDOM,
+            Advice::Sequential => libc::MADV_SEQUENTIAL,
+        };
+        let data = self.storage().as_bytes();
+        madvise(data, 0, data.len() as u64, advice)
+    }
+
+    /// Read part or all of the CDB into memory, returning once it is
+    /// resident.
+    ///
+    /// This is only available on Unix.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let cdb = cdb::CDB::open("tests/test1.cdb").unwrap();
+    /// cdb.prefault(cdb::Prefault::HashTables).unwrap();
+    /// ```
+    pub fn prefault(&self, prefault: Prefault) -> Result<()> {
+        let data = self.storage().as_bytes();
+        let size = data.len() as u64;
+        let header_size = self.format().header_size();
+        let ranges = match prefault {
+            Prefault::All => [(0, size), (0, 0)],
+            Prefault::HashTables => {
+                let data_end = Scan::data_end(self)?;
+                [(0, header_size), (data_end, size - data_end)]
+            }
+        };
+        for (pos, len) in ranges {
+            madvise(data, pos, len, libc::MADV_WILLNEED)?;
+        }
+        for (pos, len) in ranges {
+            touch(data, pos, len);
+        }
+        Ok(())
+    }
+
+    /// Report how much of the CDB is resident in memory.
+    ///
+    /// This is only available on Unix.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let cdb = cdb::CDB::open("tests/test1.cdb").unwrap();
+    /// cdb.prefault(cdb::Prefault::All).unwrap();
+    /// let residency = cdb.residency().unwrap();
+    /// assert_eq!(residency.resident, residency.size);
+    /// ```
+    pub fn residency(&self) -> Result<Residency> {
+        let data = self.storage().as_bytes();
+        let size = data.len() as u64;
+        let (addr, len) = match page_range(data, 0, size) {
+            Some(range) => range,
+            None => return Ok(Residency { size, resident: 0 }),
+        };
+        let page = page_size();
+        let mut pages = vec![0_u8; len.div_ceil(page)];
+        // SAFETY: the range covers whole pages of the slice, and the
+        // vector has one entry for each of them.
+        if unsafe { libc::mincore(addr, len, pages.as_mut_ptr() as *mut _) } != 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        // Count the bytes of the slice in each resident page.
+        let start = data.as_ptr() as usize;
+        let end = start + data.len();
+        let resident = pages
+            .iter()
+            .enumerate()
+            .filter(|(_, resident)| **resident & 1 != 0)
+            .map(|(i, _)| {
+                let page_start = (addr as usize + i * page).max(start);
+                let page_end = (addr as usize + (i + 1) * page).min(end);
+                (page_end - page_start) as u64
+            })
+            .sum();
+        Ok(Residency { size, resident })
+    }
+}
+
+/// A read-only memory mapped file, created with
+/// [`MapOptions`](struct.MapOptions.html).
+///
+/// This type is only available on Unix.
+pub struct MappedFile {
+    ptr: *const u8,
+    len: usize,
+}
+
+// SAFETY: the mapping is read-only and owned by this value.
+unsafe impl Send for MappedFile {}
+unsafe impl Sync for MappedFile {}
+
+impl AsRef<[u8]> for MappedFile {
+    fn as_ref(&self) -> &[u8] {
+        if self.len == 0 {
+            return &[];
+        }
+        // SAFETY: the mapping stays valid until the value is dropped.
+        unsafe { slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl Drop for MappedFile {
+    fn drop(&mut self) {
+        if self.len > 0 {
+            // SAFETY: this value owns the mapping, which is no longer
+            // borrowed. Unmapping also releases any locks on it.
+            unsafe { libc::munmap(self.ptr as *mut libc::c_void, self.len) };
+        }
+    }
+}
+
+/// Options for memory mapping a CDB file, in the style of
+/// `std::fs::OpenOptions`.
+///
+/// This type is only available on Unix.
+///
+/// # Example
+///
+/// ```
+/// use cdb::{Advice, MapOptions, Prefault};
+///
+/// let cdb = MapOptions::new()
+///     .advice(Advice::Random)
+///     .prefault(Prefault::HashTables)
+///     .open("tests/test1.cdb")
+///     .unwrap();
+/// assert_eq!(cdb.get(b"two").unwrap().unwrap(), b"Goodbye");
+/// ```
+#[derive(Clone, Debug)]
+pub struct MapOptions {
+    format: Format,
+    advice: Advice,
+    prefault: Option<Prefault>,
+    populate: bool,
+    lock: bool,
+}
+
+impl Default for MapOptions {
+    fn default() -> Self {
+        MapOptions::new()
+    }
+}
+
+impl MapOptions {
+    /// Create the default options: a classic format CDB with normal
+    /// access advice, which is neither prefaulted nor locked.
+    pub fn new() -> MapOptions {
+        MapOptions {
+            format: Format::Cdb32,
+            advice: Advice::Normal,
+            prefault: None,
+            populate: false,
+            lock: false,
+        }
+    }
+
+    /// Set the format of the CDB.
+    pub fn format(&mut self, format: Format) -> &mut Self {
+        self.format = format;
+        self
+    }
+
+    /// Set the access advice given for the mapping.
+    pub fn advice(&mut self, advice: Advice) -> &mut Self {
+        self.advice = advice;
+        self
+    }
+
+    /// Read part or all of the file into memory when it is opened.
+    pub fn prefault(&mut self, prefault: Prefault) -> &mut Self {
+        self.prefault = Some(prefault);
+        self
+    }
+
+    /// Have the kernel read the whole file into memory as it is mapped
+    /// (`MAP_POPULATE`). On systems other than Linux, the whole file is
+    /// prefaulted after it is mapped instead.
+    pub fn populate(&mut self, populate: bool) -> &mut Self {
+        self.populate = populate;
+        self
+    }
+
+    /// Lock the whole file in memory (`mlock`) until it is closed. This
+    /// usually requires privileges or a raised `RLIMIT_MEMLOCK`.
+    pub fn lock(&mut self, lock: bool) -> &mut Self {
+        self.lock = lock;
+        self
+    }
+
+    /// Map the named file only.
+    pub fn map<P: AsRef<path::Path>>(&self, filename: P) -> Result<MappedFile> {
+        self.map_file(&fs::File::open(filename)?)
+    }
+
+    /// Map an already opened file only. The mapping remains valid after
+    /// the file is closed.
+    pub fn map_file(&self, file: &fs::File) -> Result<MappedFile> {
+        let len = file.metadata()?.len();
+        let len = usize::try_from(len).map_err(|_| Error::SizeLimit)?;
+        if len == 0 {
+            return Ok(MappedFile {
+                ptr: ptr::null(),
+                len,
+            });
+        }
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        let flags = if self.populate {
+            libc::MAP_SHARED | libc::MAP_POPULATE
+        } else {
+            libc::MAP_SHARED
+        };
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        let flags = libc::MAP_SHARED;
+        // SAFETY: a new read-only mapping of an open file does not
+        // alias any memory in use.
+        let ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                flags,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error().into());
+        }
+        let mapped = MappedFile {
+            ptr: ptr as *const u8,
+            len,
+        };
+        // SAFETY: the range is exactly the mapping created above.
+        if self.lock && unsafe { libc::mlock(ptr, len) } != 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        Ok(mapped)
+    }
+
+    /// Map the named file and return the CDB reader.
+    pub fn open<P: AsRef<path::Path>>(&self, filename: P) -> Result<CDB<MappedFile>> {
+        let cdb = CDB::with_format(self.map(filename)?, self.format)?;
+        cdb.advise(self.advice)?;
+        // Without MAP_POPULATE, populate the mapping by prefaulting it.
+        let prefault = if self.populate && !cfg!(any(target_os = "linux", target_os = "android")) {
+            Some(Prefault::All)
+        } else {
+            self.prefault
+        };
+        if let Some(prefault) = prefault {
+            cdb.prefault(prefault)?;
+        }
+        Ok(cdb)
+    }
+}
@@ -0,0 +1,103 @@
+use rayon::prelude::*;
+
+use crate::hash::CDBHash;
+use crate::reader::{CDB, CDBKeyValueIter, CDBKeyValueRefIter, Result, Scan};
+use crate::storage::{SliceStorage, Storage};
+
+/// The number of hash table slots sampled for split points per thread.
+const SAMPLES_PER_THREAD: u64 = 16;
+
+impl<S: Storage + Sync, H: CDBHash + Sync> CDB<S, H> {
+    /// Iterate over all the `(key, value)` pairs in the database in
+    /// parallel.
+    ///
+    /// Records vary in length, so the record region cannot simply be
+    /// cut into equal parts. Instead, a sample of the record pointers
+    /// in the hash tables is used to split it into many pieces at
+    /// record boundaries, which are then scanned in parallel. When
+    /// collected, the records are in the same order as from
+    /// [`iter`](#method.iter).
+    ///
+    /// This method is only available with the `rayon` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rayon::prelude::*;
+    ///
+    /// let cdb = cdb::CDB::open("tests/test1.cdb").unwrap();
+    /// let total: usize = cdb
+    ///     .par_iter()
+    ///     .map(|result| result.unwrap().1.len())
+    ///     .sum();
+    /// println!("{} bytes of values", total);
+    /// ```
+    pub fn par_iter(&self) -> impl ParallelIterator<Item = Result<(Vec<u8>, Vec<u8>)>> + '_ {
+        self.split_scans()
+            .into_par_iter()
+            .flat_map_iter(move |scan| CDBKeyValueIter::with_scan(self, scan))
+    }
+
+    /// Split the record region into scans of roughly equal size.
+    fn split_scans(&self) -> Vec<Scan> {
+        match self.split_points() {
+            Ok(points) => points
+                .windows(2)
+                .map(|range| Scan::range(range[0], range[1]))
+                .collect(),
+            // Leave the error to be reported by a plain scan.
+            Err(_) => vec![Scan::new()],
+        }
+    }
+
+    /// Find a sorted list of record boundaries, including the start and
+    /// end of the record region.
+    fn split_points(&self) -> Result<Vec<u64>> {
+        let pair_size = self.format().pair_size();
+        let header_size = self.format().header_size();
+        let data_end = Scan::data_end(self)?.max(header_size);
+
+        let mut tables = Vec::with_capacity(256);
+        let mut total = 0_u64;
+        for i in 0..256 {
+            let (hpos, hslots) = self.read_table(i)?;
+            tables.push((hpos, hslots));
+            total = total.saturating_add(hslots);
+        }
+
+        // Sample slots evenly spaced across all the hash tables. As
+        // keys are placed by their hash, the records they point at are
+        // spread evenly through the record region.
+        let samples = rayon::current_num_threads() as u64 * SAMPLES_PER_THREAD;
+        let mut points = vec![header_size, data_end];
+        let (mut table, mut base) = (0, 0_u64);
+        for i in 0..samples.min(total) {
+            let slot = (i as u128 * total as u128 / samples as u128) as u64;
+            while slot >= base.saturating_add(tables[table].1) {
+                base += tables[table].1;
+                table += 1;
+            }
+            let (_, pos) = self.read_pair(tables[table].0 + (slot - base) * pair_size)?;
+            if pos > header_size && pos < data_end && self.is_record(pos, data_end)? {
+                points.push(pos);
+            }
+        }
+        points.sort_unstable();
+        points.dedup();
+        Ok(points)
+    }
+}
+
+impl<S: SliceStorage + Sync, H: CDBHash + Sync> CDB<S, H> {
+    /// Iterate over all the `(key, value)` pairs in the database in
+    /// parallel, producing slices borrowed from the underlying file.
+    ///
+    /// See [`par_iter`](#method.par_iter)
+    ///
+    /// This method is only available with the `rayon` feature.
+    pub fn par_iter_ref(&self) -> impl ParallelIterator<Item = Result<(&[u8], &[u8])>> + '_ {
+        self.split_scans()
+            .into_par_iter()
+            .flat_map_iter(move |scan| CDBKeyValueRefIter::with_scan(self, scan))
+    }
+}
@@ -1,36 +1,56 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::min;
+#[cfg(feature = "std")]
 use filebuffer::FileBuffer;
-use std::cmp::min;
-use std::io;
+#[cfg(feature = "std")]
 use std::path;
 
-use crate::hash::hash;
-use crate::uint32;
+use crate::error::Error;
 
-pub use std::io::Result;
+use crate::format::Format;
+use crate::hash::{CDBHash, DjbHash, TRAILER_SIZE, parse_trailer};
+#[cfg(feature = "std")]
+use crate::storage::PreadFile;
+use crate::storage::{SliceStorage, Storage};
+#[cfg(feature = "std")]
+use crate::value::ValueReader;
+
+pub use crate::error::Result;
 
 const KEYSIZE: usize = 32;
 
 /// CDB file reader
 ///
+/// The reader is generic over its [`Storage`](trait.Storage.html),
+/// which defaults to a static byte slice, such as one from
+/// `include_bytes!`. [`open`](#method.open) returns a reader over a
+/// memory mapped file, a `CDB<FileBuffer>`.
+///
 /// # Example
 ///
 /// ```
+/// # #[cfg(feature = "std")] {
 /// let cdb = cdb::CDB::open("tests/test1.cdb").unwrap();
 ///
 /// for result in cdb.find(b"one") {
 ///     println!("{:?}", result.unwrap());
 /// }
+/// # }
 /// ```
-pub struct CDB {
-    file: FileBuffer,
-    size: usize,
+pub struct CDB<S = &'static [u8], H = DjbHash> {
+    file: S,
+    size: u64,
+    format: Format,
+    hasher: H,
 }
 
-fn err_badfile<T>() -> Result<T> {
-    Err(io::Error::new(io::ErrorKind::Other, "Invalid file format"))
+fn err_range<T>(offset: u64) -> Result<T> {
+    Err(Error::OutOfRange { offset })
 }
 
-impl CDB {
+#[cfg(feature = "std")]
+impl CDB<FileBuffer> {
     /// Opens the named file and returns the CDB reader.
     ///
     /// # Examples
@@ -38,38 +58,251 @@ impl CDB {
     /// ```
     /// let cdb = cdb::CDB::open("tests/test1.cdb").unwrap();
     /// ```
-    pub fn open<P: AsRef<path::Path>>(filename: P) -> Result<CDB> {
-        let file = FileBuffer::open(&filename)?;
-        if file.len() < 2048 + 8 + 8 || file.len() > 0xffffffff {
-            return err_badfile();
+    pub fn open<P: AsRef<path::Path>>(filename: P) -> Result<CDB<FileBuffer>> {
+        CDB::new(FileBuffer::open(&filename)?)
+    }
+
+    /// Opens the named file and returns the CDB reader, detecting
+    /// whether it is in the classic or the 64-bit format.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let cdb = cdb::CDB::open_detect("tests/test1.cdb").unwrap();
+    /// assert_eq!(cdb.format(), cdb::Format::Cdb32);
+    /// ```
+    pub fn open_detect<P: AsRef<path::Path>>(filename: P) -> Result<CDB<FileBuffer>> {
+        CDB::detect(FileBuffer::open(&filename)?)
+    }
+}
+
+#[cfg(feature = "std")]
+impl CDB<PreadFile> {
+    /// Opens the named file for reading with positional reads (`pread`)
+    /// instead of a memory map.
+    ///
+    /// A memory mapped file that is truncated or overwritten while it
+    /// is open can crash the process with `SIGBUS`. With this reader,
+    /// such changes are instead reported as I/O errors from the lookup
+    /// methods.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let cdb = cdb::CDB::open_pread("tests/test1.cdb").unwrap();
+    /// let mut value = Vec::new();
+    /// assert!(cdb.get_into(b"two", &mut value).unwrap());
+    /// assert_eq!(value, b"Goodbye");
+    /// ```
+    pub fn open_pread<P: AsRef<path::Path>>(filename: P) -> Result<CDB<PreadFile>> {
+        CDB::new(PreadFile::open(filename)?)
+    }
+}
+
+impl CDB<&'static [u8]> {
+    /// Creates a CDB reader over a static byte slice holding a CDB in
+    /// the classic format, written with the classic hash function.
+    ///
+    /// Unlike [`new`](#method.new), this is a `const fn` which checks
+    /// nothing, so the reader can be created at compile time and has
+    /// no cost at startup. Lookups in a slice which does not hold a
+    /// valid CDB return errors, and never panic.
+    ///
+    /// Only CDBs written with [`DjbHash`](struct.DjbHash.html), such as
+    /// those built by [`compile`](fn.compile.html), are supported. The
+    /// hash function recorded in the file is not checked, so lookups in
+    /// a CDB written with any other hash function silently miss keys.
+    /// Use [`with_hasher`](#method.with_hasher) to read those.
+    ///
+    /// See [`include_cdb!`](macro.include_cdb.html)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// static CDB: cdb::CDB<&[u8]> = cdb::CDB::from_static(include_bytes!("../tests/test1.cdb"));
+    ///
+    /// assert_eq!(CDB.get_ref(b"two").unwrap().unwrap(), b"Goodbye");
+    /// ```
+    pub const fn from_static(data: &'static [u8]) -> CDB<&'static [u8]> {
+        CDB {
+            file: data,
+            size: data.len() as u64,
+            format: Format::Cdb32,
+            hasher: DjbHash,
         }
-        let size = file.len();
-        Ok(CDB { file, size })
     }
+}
 
-    fn read(&self, buf: &mut [u8], pos: u32) -> Result<usize> {
-        let len = buf.len();
-        let pos = pos as usize;
-        if pos + len > self.size {
-            return err_badfile();
+/// Embed a CDB built by a build script with
+/// [`compile`](fn.compile.html), returning a static reader for it.
+///
+/// The argument is the name the CDB was given when it was compiled.
+/// This expands to a call to
+/// [`CDB::from_static`](struct.CDB.html#method.from_static), so it can
+/// be used to initialize a `static`. It works without the `std` feature,
+/// as long as the build script has it.
+///
+/// # Example
+///
+/// ```ignore
+/// static COLORS: cdb::CDB<&[u8]> = cdb::include_cdb!("colors");
+/// ```
+#[macro_export]
+macro_rules! include_cdb {
+    ( $name:expr ) => {
+        $crate::CDB::from_static(include_bytes!(concat!(env!("OUT_DIR"), "/", $name, ".cdb")))
+    };
+}
+
+impl<S: Storage> CDB<S> {
+    /// Creates a CDB reader over the given storage.
+    ///
+    /// The storage may be any owner of bytes, such as a `&'static [u8]`
+    /// from `include_bytes!`, a `Vec<u8>` or a `Box<[u8]>`, or any
+    /// other implementation of [`Storage`](trait.Storage.html).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let data = std::fs::read("tests/test1.cdb").unwrap();
+    /// let cdb = cdb::CDB::new(data).unwrap();
+    /// assert_eq!(cdb.get(b"two").unwrap().unwrap(), b"Goodbye");
+    /// ```
+    pub fn new(file: S) -> Result<CDB<S>> {
+        CDB::with_format(file, Format::Cdb32)
+    }
+
+    /// Creates a CDB reader over the given storage, which holds a CDB
+    /// in the given format.
+    pub fn with_format(file: S, format: Format) -> Result<CDB<S>> {
+        CDB::with_hasher(file, format, DjbHash)
+    }
+
+    /// Creates a CDB reader over the given storage, detecting the
+    /// format of the CDB it holds.
+    ///
+    /// See [`Format::detect`](enum.Format.html#method.detect)
+    pub fn detect(file: S) -> Result<CDB<S>> {
+        let format = Format::detect(&file)?;
+        CDB::with_format(file, format)
+    }
+}
+
+impl<S: Storage, H: CDBHash> CDB<S, H> {
+    /// Creates a CDB reader over the given storage, which holds a CDB
+    /// in the given format that was written using the given hash
+    /// function.
+    ///
+    /// Files written with a hash function other than the classic one
+    /// record which function was used. It is an error to open a file
+    /// with a different hash function than the one it was written with.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cdb::{CDB, Format, Xxh32Hash};
+    ///
+    /// let data = std::fs::read("tests/test1.cdb").unwrap();
+    /// assert!(CDB::with_hasher(&data, Format::Cdb32, Xxh32Hash::new(1)).is_err());
+    /// ```
+    pub fn with_hasher(file: S, format: Format, hasher: H) -> Result<CDB<S, H>> {
+        let size = file.size();
+        if size < format.header_size() {
+            return Err(Error::Truncated);
+        }
+        if size > format.max_pos() {
+            return Err(Error::SizeLimit);
+        }
+        let cdb = CDB {
+            file,
+            size,
+            format,
+            hasher,
+        };
+        if cdb.hash_id()? != cdb.hasher.id() {
+            return Err(Error::HashMismatch);
+        }
+        Ok(cdb)
+    }
+
+    /// Read the identifier of the hash function recorded after the
+    /// last hash table, if any.
+    fn hash_id(&self) -> Result<Option<[u8; 8]>> {
+        let pair_size = self.format.pair_size();
+        let (hpos, hslots) = self.read_pair(255 * pair_size)?;
+        let end = hslots
+            .checked_mul(pair_size)
+            .and_then(|len| len.checked_add(hpos));
+        let mut trailer = [0_u8; TRAILER_SIZE];
+        match end {
+            Some(end) if self.read(&mut trailer, end).is_ok() => Ok(parse_trailer(&trailer)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns the hash function used for keys.
+    pub fn hasher(&self) -> &H {
+        &self.hasher
+    }
+
+    /// Returns the format of the CDB.
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    /// Returns a reference to the underlying storage.
+    pub fn storage(&self) -> &S {
+        &self.file
+    }
+
+    /// Check that a range lies within the file.
+    fn check_range(&self, pos: u64, len: u64) -> Result<()> {
+        match pos.checked_add(len) {
+            Some(end) if end <= self.size => Ok(()),
+            _ => err_range(pos),
         }
-        buf.copy_from_slice(&self.file[pos..pos + len]);
+    }
+
+    fn read(&self, buf: &mut [u8], pos: u64) -> Result<usize> {
+        let len = buf.len();
+        self.check_range(pos, len as u64)?;
+        self.file.read_at(buf, pos)?;
         Ok(len)
     }
 
-    fn hash_table(&self, khash: u32) -> (u32, u32, u32) {
-        let x = ((khash as usize) & 0xff) << 3;
-        let (hpos, hslots) = uint32::unpack2(&self.file[x..x + 8]);
+    /// Read a pair of numbers in the format of this CDB.
+    pub(crate) fn read_pair(&self, pos: u64) -> Result<(u64, u64)> {
+        let mut buf = [0_u8; 16];
+        let buf = &mut buf[..self.format.pair_size() as usize];
+        self.read(buf, pos)?;
+        Ok(self.format.unpack2(buf))
+    }
+
+    /// Read the position and number of slots of one of the 256 hash
+    /// tables, after checking that the table lies within the file.
+    pub(crate) fn read_table(&self, table: u64) -> Result<(u64, u64)> {
+        let pair_size = self.format.pair_size();
+        let (hpos, hslots) = self.read_pair(table * pair_size)?;
+        match hslots.checked_mul(pair_size) {
+            Some(len) => self.check_range(hpos, len)?,
+            None => return err_range(hpos),
+        }
+        Ok((hpos, hslots))
+    }
+
+    pub(crate) fn hash_table(&self, khash: u32) -> Result<(u64, u64, u64)> {
+        let pair_size = self.format.pair_size();
+        let (hpos, hslots) = self.read_table((khash & 0xff) as u64)?;
         let kpos = if hslots > 0 {
-            hpos + (((khash >> 8) % hslots) << 3)
+            hpos + ((khash >> 8) as u64 % hslots) * pair_size
         } else {
             0
         };
-        (hpos, hslots, kpos)
+        Ok((hpos, hslots, kpos))
     }
 
-    fn match_key(&self, key: &[u8], pos: u32) -> Result<bool> {
-        let mut buf = [0 as u8; KEYSIZE];
+    fn match_key(&self, key: &[u8], pos: u64) -> Result<bool> {
+        let mut buf = [0_u8; KEYSIZE];
         let mut len = key.len();
         let mut pos = pos;
         let mut keypos = 0;
@@ -80,102 +313,432 @@ impl CDB {
             if buf[..n] != key[keypos..keypos + n] {
                 return Ok(false);
             }
-            pos += n as u32;
+            pos += n as u64;
             keypos += n;
             len -= n;
         }
         Ok(true)
     }
 
+    /// Check whether the record at the given position has the key,
+    /// returning the position and length of its value if it does.
+    pub(crate) fn match_record(&self, key: &[u8], pos: u64) -> Result<Option<(u64, u64)>> {
+        let (klen, dlen) = self.read_pair(pos)?;
+        let kpos = pos + self.format.pair_size();
+        if klen == key.len() as u64 && self.match_key(key, kpos)? {
+            Ok(Some((kpos + klen, dlen)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub(crate) fn read_vec(&self, pos: u64, len: u64) -> Result<Vec<u8>> {
+        let mut result = Vec::new();
+        self.read_into(pos, len, &mut result)?;
+        Ok(result)
+    }
+
+    fn read_into(&self, pos: u64, len: u64, buf: &mut Vec<u8>) -> Result<()> {
+        // Check the range before allocating, so that a corrupt length
+        // cannot cause a huge allocation.
+        self.check_range(pos, len)?;
+        buf.clear();
+        buf.resize(usize::try_from(len).map_err(|_| Error::SizeLimit)?, 0);
+        self.read(&mut buf[..], pos)?;
+        Ok(())
+    }
+
     /// Find the first record with the named key.
     ///
     /// # Examples
     ///
     /// ```
+    /// # #[cfg(feature = "std")] {
     /// let cdb = cdb::CDB::open("tests/test1.cdb").unwrap();
     /// if let Some(record) = cdb.get(b"one") {
     ///     println!("{:?}", record.unwrap());
     /// }
+    /// # }
     /// ```
     pub fn get(&self, key: &[u8]) -> Option<Result<Vec<u8>>> {
         self.find(key).next()
     }
 
+    /// Find the first record with the named key, reading its value
+    /// into the given buffer. The previous contents of the buffer are
+    /// replaced, but its allocation is reused. Returns `false` if the
+    /// key was not found.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "std")] {
+    /// let cdb = cdb::CDB::open("tests/test1.cdb").unwrap();
+    /// let mut value = Vec::new();
+    /// if cdb.get_into(b"one", &mut value).unwrap() {
+    ///     println!("{:?}", value);
+    /// }
+    /// # }
+    /// ```
+    pub fn get_into(&self, key: &[u8], buf: &mut Vec<u8>) -> Result<bool> {
+        self.find(key).next_into(buf)
+    }
+
     /// Find all records with the named key. The returned iterator
     /// produces each value associated with the key.
     ///
     /// # Examples
     ///
     /// ```
+    /// # #[cfg(feature = "std")] {
     /// let cdb = cdb::CDB::open("tests/test1.cdb").unwrap();
     ///
     /// for result in cdb.find(b"one") {
     ///     println!("{:?}", result.unwrap());
     /// }
+    /// # }
     /// ```
-    pub fn find(&self, key: &[u8]) -> CDBValueIter<'_> {
+    pub fn find(&self, key: &[u8]) -> CDBValueIter<'_, S, H> {
         CDBValueIter::find(self, key)
     }
 
+    /// Returns true if there is at least one record with the named
+    /// key. No value is read.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "std")] {
+    /// let cdb = cdb::CDB::open("tests/test1.cdb").unwrap();
+    /// assert!(cdb.contains_key(b"one").unwrap());
+    /// assert!(!cdb.contains_key(b"four").unwrap());
+    /// # }
+    /// ```
+    pub fn contains_key(&self, key: &[u8]) -> Result<bool> {
+        Ok(self.value_lens(key).next().transpose()?.is_some())
+    }
+
+    /// Count the records with the named key. No value is read.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "std")] {
+    /// let cdb = cdb::CDB::open("tests/test1.cdb").unwrap();
+    /// assert_eq!(cdb.count(b"one").unwrap(), 2);
+    /// # }
+    /// ```
+    pub fn count(&self, key: &[u8]) -> Result<u64> {
+        self.value_lens(key)
+            .try_fold(0, |count, len| len.map(|_| count + 1))
+    }
+
+    /// Find all records with the named key. The returned iterator
+    /// produces the length of each value associated with the key,
+    /// without reading the value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "std")] {
+    /// let cdb = cdb::CDB::open("tests/test1.cdb").unwrap();
+    /// for len in cdb.value_lens(b"one") {
+    ///     println!("{}", len.unwrap());
+    /// }
+    /// # }
+    /// ```
+    pub fn value_lens(&self, key: &[u8]) -> CDBValueLenIter<'_, S, H> {
+        CDBValueLenIter {
+            cdb: self,
+            key: key.to_vec(),
+            lookup: Lookup::new(self.hasher.hash(key)),
+        }
+    }
+
+    /// Find the offsets of all records with the named key. Neither the
+    /// keys nor the values are copied.
+    ///
+    /// The offsets can be kept and later passed to
+    /// [`record_at`](#method.record_at).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "std")] {
+    /// let cdb = cdb::CDB::open("tests/test1.cdb").unwrap();
+    /// for offset in cdb.find_offsets(b"one") {
+    ///     let (key, value) = cdb.record_at(offset.unwrap()).unwrap();
+    ///     assert_eq!(key, b"one");
+    /// }
+    /// # }
+    /// ```
+    pub fn find_offsets(&self, key: &[u8]) -> CDBOffsetIter<'_, S, H> {
+        CDBOffsetIter {
+            cdb: self,
+            state: OffsetState::Find(key.to_vec(), Lookup::new(self.hasher.hash(key))),
+        }
+    }
+
+    /// Iterate over the offsets of all the records in the database,
+    /// in file order. Neither the keys nor the values are copied.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "std")] {
+    /// let cdb = cdb::CDB::open("tests/test1.cdb").unwrap();
+    /// assert_eq!(cdb.iter_offsets().next().unwrap().unwrap(), 2048);
+    /// # }
+    /// ```
+    pub fn iter_offsets(&self) -> CDBOffsetIter<'_, S, H> {
+        CDBOffsetIter {
+            cdb: self,
+            state: OffsetState::Scan(Scan::new()),
+        }
+    }
+
+    /// Read the key and value of the record at the given offset.
+    ///
+    /// The offset must have come from
+    /// [`find_offsets`](#method.find_offsets),
+    /// [`iter_offsets`](#method.iter_offsets) or a
+    /// [`Cursor`](struct.Cursor.html). It is an error for the record
+    /// to lie outside of the record region, but an offset which is
+    /// not at the start of a record is not otherwise detected.
+    pub fn record_at(&self, offset: u64) -> Result<(Vec<u8>, Vec<u8>)> {
+        let (kpos, klen, dlen) = self.record_bounds(offset)?;
+        let key = self.read_vec(kpos, klen)?;
+        let value = self.read_vec(kpos + klen, dlen)?;
+        Ok((key, value))
+    }
+
+    /// Find the position of the key, and the lengths of the key and
+    /// value, of the record at the given offset.
+    fn record_bounds(&self, offset: u64) -> Result<(u64, u64, u64)> {
+        let data_end = Scan::data_end(self)?;
+        if offset < self.format.header_size() || offset >= data_end {
+            return err_range(offset);
+        }
+        let (klen, dlen) = self.read_pair(offset)?;
+        let kpos = offset + self.format.pair_size();
+        match kpos.checked_add(klen).and_then(|end| end.checked_add(dlen)) {
+            Some(end) if end <= data_end => Ok((kpos, klen, dlen)),
+            _ => Err(Error::RecordPastEnd { offset }),
+        }
+    }
+
+    /// Returns the number of records in the database.
+    ///
+    /// This is computed from the sizes of the hash tables in the
+    /// header, which every CDB writer makes twice the number of
+    /// records in the table, so no records or slots are read.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "std")] {
+    /// let cdb = cdb::CDB::open("tests/test1.cdb").unwrap();
+    /// assert_eq!(cdb.len().unwrap(), 4);
+    /// # }
+    /// ```
+    pub fn len(&self) -> Result<u64> {
+        let pair_size = self.format.pair_size();
+        let mut header = vec![0; self.format.header_size() as usize];
+        self.read(&mut header, 0)?;
+        Ok(header
+            .chunks(pair_size as usize)
+            .map(|entry| self.format.unpack2(entry).1 / 2)
+            .fold(0, u64::saturating_add))
+    }
+
+    /// Returns true if there are no records in the database.
+    pub fn is_empty(&self) -> Result<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Find the first record with the named key, using a hash of the
+    /// key computed earlier with [`hasher`](#method.hasher).
+    pub fn get_with_hash(&self, key: &[u8], hash: u32) -> Option<Result<Vec<u8>>> {
+        self.find_with_hash(key, hash).next()
+    }
+
+    /// Find all records with the named key, using a hash of the key
+    /// computed earlier with [`hasher`](#method.hasher).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "std")] {
+    /// use cdb::CDBHash;
+    ///
+    /// let cdb = cdb::CDB::open("tests/test1.cdb").unwrap();
+    /// let hash = cdb.hasher().hash(b"one");
+    /// for result in cdb.find_with_hash(b"one", hash) {
+    ///     println!("{:?}", result.unwrap());
+    /// }
+    /// # }
+    /// ```
+    pub fn find_with_hash(&self, key: &[u8], hash: u32) -> CDBValueIter<'_, S, H> {
+        CDBValueIter {
+            cdb: self,
+            key: key.to_vec(),
+            lookup: Lookup::new(hash),
+        }
+    }
+
     /// Iterate over all the `(key, value)` pairs in the database.
     ///
     /// # Examples
     ///
     /// ```
+    /// # #[cfg(feature = "std")] {
     /// let cdb = cdb::CDB::open("tests/test1.cdb").unwrap();
     /// for result in cdb.iter() {
     ///     let (key, value) = result.unwrap();
     ///     println!("{:?} => {:?}", key, value);
     /// }
+    /// # }
     /// ````
-    pub fn iter(&self) -> CDBKeyValueIter<'_> {
-        CDBKeyValueIter::start(&self)
+    pub fn iter(&self) -> CDBKeyValueIter<'_, S, H> {
+        CDBKeyValueIter::start(self)
     }
-}
 
-/// Type alias for [`CDBValueiter`](struct.CDBValueIter.html)
-pub type CDBIter<'a> = CDBValueIter<'a>;
+    /// Iterate over the `(key, value)` pairs in the database, resuming
+    /// from a cursor taken from an earlier iteration.
+    ///
+    /// The cursor is checked to point at the start of a record, or at
+    /// the end of the records, in this database. It is an error if it
+    /// does not.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "std")] {
+    /// let cdb = cdb::CDB::open("tests/test1.cdb").unwrap();
+    /// let mut iter = cdb.iter();
+    /// iter.next();
+    /// let cursor = iter.cursor();
+    ///
+    /// // Later, perhaps in another process:
+    /// let cursor = cdb::Cursor::new(cursor.offset());
+    /// for result in cdb.iter_from(cursor).unwrap() {
+    ///     let (key, value) = result.unwrap();
+    ///     println!("{:?} => {:?}", key, value);
+    /// }
+    /// # }
+    /// ```
+    pub fn iter_from(&self, cursor: Cursor) -> Result<CDBKeyValueIter<'_, S, H>> {
+        Ok(CDBKeyValueIter::with_scan(self, Scan::at(self, cursor)?))
+    }
 
-/// Iterator over a set of records in the CDB with the same key.
-///
-/// See [`CDB::find`](struct.CDB.html#method.find)
-pub struct CDBValueIter<'a> {
-    cdb: &'a CDB,
-    key: Vec<u8>,
-    khash: u32,
-    kloop: u32,
-    kpos: u32,
-    hpos: u32,
-    hslots: u32,
-    dpos: u32,
-    dlen: u32,
+    /// Check that a position is the start of a record, by looking up
+    /// its key and finding the record among the results.
+    pub(crate) fn is_record(&self, pos: u64, data_end: u64) -> Result<bool> {
+        let pair_size = self.format.pair_size();
+        if pos.checked_add(pair_size).is_none_or(|end| end > data_end) {
+            return Ok(false);
+        }
+        let (klen, dlen) = self.read_pair(pos)?;
+        let kpos = pos + pair_size;
+        match kpos.checked_add(klen).and_then(|end| end.checked_add(dlen)) {
+            Some(end) if end <= data_end => (),
+            _ => return Ok(false),
+        }
+        let key = self.read_vec(kpos, klen)?;
+        let mut lookup = Lookup::new(self.hasher.hash(&key));
+        while let Some(found) = lookup.next(self, &key) {
+            if found?.0 == kpos + klen {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
 }
 
-impl<'a> CDBValueIter<'a> {
-    fn find(cdb: &'a CDB, key: &[u8]) -> Self {
-        let khash = hash(key);
-        let (hpos, hslots, kpos) = cdb.hash_table(khash);
+impl<S: SliceStorage, H: CDBHash> CDB<S, H> {
+    pub(crate) fn slice(&self, pos: u64, len: u64) -> Result<&[u8]> {
+        self.check_range(pos, len)?;
+        Ok(&self.file.as_bytes()[pos as usize..(pos + len) as usize])
+    }
 
-        CDBValueIter {
-            cdb: cdb,
-            key: key.into_iter().map(|x| *x).collect(),
-            khash: khash,
-            kloop: 0,
-            kpos: kpos,
-            hpos: hpos,
-            hslots: hslots,
-            dpos: 0,
-            dlen: 0,
-        }
+    /// Find the first record with the named key, returning a slice of
+    /// the value borrowed from the underlying file instead of a copy.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "std")] {
+    /// let cdb = cdb::CDB::open("tests/test1.cdb").unwrap();
+    /// if let Some(record) = cdb.get_ref(b"one") {
+    ///     assert_eq!(record.unwrap(), b"Hello");
+    /// }
+    /// # }
+    /// ```
+    pub fn get_ref(&self, key: &[u8]) -> Option<Result<&[u8]>> {
+        let mut lookup = Lookup::new(self.hasher.hash(key));
+        lookup
+            .next(self, key)
+            .map(|r| r.and_then(|(dpos, dlen)| self.slice(dpos, dlen)))
     }
 
-    fn read_vec(&self) -> Result<Vec<u8>> {
-        let mut result = vec![0; self.dlen as usize];
-        self.cdb.read(&mut result[..], self.dpos)?;
-        Ok(result)
+    /// Find all records with the named key. The returned iterator
+    /// produces slices of each value borrowed from the underlying file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "std")] {
+    /// let cdb = cdb::CDB::open("tests/test1.cdb").unwrap();
+    ///
+    /// for result in cdb.find_ref(b"one") {
+    ///     println!("{:?}", result.unwrap());
+    /// }
+    /// # }
+    /// ```
+    pub fn find_ref<'a, 'k>(&'a self, key: &'k [u8]) -> CDBValueRefIter<'a, 'k, S, H> {
+        CDBValueRefIter::find(self, key)
+    }
+
+    /// Iterate over all the `(key, value)` pairs in the database,
+    /// producing slices borrowed from the underlying file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "std")] {
+    /// let cdb = cdb::CDB::open("tests/test1.cdb").unwrap();
+    /// for result in cdb.iter_ref() {
+    ///     let (key, value) = result.unwrap();
+    ///     println!("{:?} => {:?}", key, value);
+    /// }
+    /// # }
+    /// ````
+    pub fn iter_ref(&self) -> CDBKeyValueRefIter<'_, S, H> {
+        CDBKeyValueRefIter::start(self)
+    }
+
+    /// Read the key and value of the record at the given offset,
+    /// returning slices borrowed from the underlying file.
+    ///
+    /// See [`record_at`](#method.record_at)
+    pub fn record_ref_at(&self, offset: u64) -> Result<(&[u8], &[u8])> {
+        let (kpos, klen, dlen) = self.record_bounds(offset)?;
+        Ok((self.slice(kpos, klen)?, self.slice(kpos + klen, dlen)?))
+    }
+
+    /// Iterate over the `(key, value)` pairs in the database,
+    /// producing slices borrowed from the underlying file, resuming
+    /// from a cursor taken from an earlier iteration.
+    ///
+    /// See [`iter_from`](#method.iter_from)
+    pub fn iter_ref_from(&self, cursor: Cursor) -> Result<CDBKeyValueRefIter<'_, S, H>> {
+        Ok(CDBKeyValueRefIter::with_scan(self, Scan::at(self, cursor)?))
     }
 }
 
+/// Type alias for [`CDBValueiter`](struct.CDBValueIter.html)
+pub type CDBIter<'a, S = &'static [u8], H = DjbHash> = CDBValueIter<'a, S, H>;
+
 macro_rules! iter_try {
     ( $e:expr ) => {
         match $e {
@@ -187,79 +750,415 @@ macro_rules! iter_try {
     };
 }
 
-impl<'a> Iterator for CDBValueIter<'a> {
-    type Item = Result<Vec<u8>>;
-    fn next(&mut self) -> Option<Self::Item> {
+/// The state of a search through the hash table for a key.
+pub(crate) struct Lookup {
+    khash: u32,
+    kloop: u64,
+    kpos: u64,
+    hpos: u64,
+    hslots: u64,
+    failed: bool,
+}
+
+impl Lookup {
+    pub(crate) fn new(khash: u32) -> Self {
+        Lookup {
+            khash,
+            kloop: 0,
+            kpos: 0,
+            hpos: 0,
+            hslots: 0,
+            failed: false,
+        }
+    }
+
+    /// Resume a search in the hash table at `hpos` with `hslots` slots,
+    /// after `kloop` slots have been probed, at the slot at `kpos`.
+    pub(crate) fn resume(khash: u32, hpos: u64, hslots: u64, kpos: u64, kloop: u64) -> Self {
+        Lookup {
+            khash,
+            kloop,
+            kpos,
+            hpos,
+            hslots,
+            failed: false,
+        }
+    }
+
+    /// Find the next record matching the key, returning the position
+    /// and length of its value.
+    ///
+    /// The search ends at the first error, so that a corrupt file
+    /// cannot produce the same error forever.
+    pub(crate) fn next<S: Storage, H: CDBHash>(
+        &mut self,
+        cdb: &CDB<S, H>,
+        key: &[u8],
+    ) -> Option<Result<(u64, u64)>> {
+        if self.failed {
+            return None;
+        }
+        let found = self.step(cdb, key).transpose();
+        self.failed = matches!(found, Some(Err(_)));
+        found
+    }
+
+    fn step<S: Storage, H: CDBHash>(
+        &mut self,
+        cdb: &CDB<S, H>,
+        key: &[u8],
+    ) -> Result<Option<(u64, u64)>> {
+        let pair_size = cdb.format.pair_size();
+        if self.kpos == 0 {
+            let (hpos, hslots, kpos) = cdb.hash_table(self.khash)?;
+            self.hpos = hpos;
+            self.hslots = hslots;
+            self.kpos = kpos;
+        }
+        // The table was checked to lie within the file, and each read
+        // below succeeding bounds the position after it, so none of
+        // this arithmetic can overflow.
+        let table_end = self.hpos + self.hslots * pair_size;
         while self.kloop < self.hslots {
-            let mut buf = [0 as u8; 8];
-            let kpos = self.kpos;
-            iter_try!(self.cdb.read(&mut buf, kpos));
-            let (khash, pos) = uint32::unpack2(&buf);
+            let (khash, pos) = cdb.read_pair(self.kpos)?;
             if pos == 0 {
-                return None;
+                return Ok(None);
             }
             self.kloop += 1;
-            self.kpos += 8;
-            if self.kpos == self.hpos + (self.hslots << 3) {
+            self.kpos += pair_size;
+            if self.kpos == table_end {
                 self.kpos = self.hpos;
             }
-            if khash == self.khash {
-                iter_try!(self.cdb.read(&mut buf, pos));
-                let (klen, dlen) = uint32::unpack2(&buf);
-                if klen as usize == self.key.len() {
-                    if iter_try!(self.cdb.match_key(&self.key[..], pos + 8)) {
-                        self.dlen = dlen;
-                        self.dpos = pos + 8 + self.key.len() as u32;
-                        return Some(self.read_vec());
-                    }
-                }
+            if khash == self.khash as u64
+                && let Some(found) = cdb.match_record(key, pos)?
+            {
+                return Ok(Some(found));
             }
         }
-        None
+        Ok(None)
     }
 }
 
-/// Iterator over all the records in the CDB.
+/// Iterator over a set of records in the CDB with the same key.
 ///
-/// See [`CDB::iter`](struct.CDB.html#method.iter)
-pub struct CDBKeyValueIter<'a> {
-    cdb: &'a CDB,
-    pos: u32,
-    data_end: u32,
+/// See [`CDB::find`](struct.CDB.html#method.find)
+pub struct CDBValueIter<'a, S = &'static [u8], H = DjbHash> {
+    cdb: &'a CDB<S, H>,
+    key: Vec<u8>,
+    lookup: Lookup,
+}
+
+impl<'a, S: Storage, H: CDBHash> CDBValueIter<'a, S, H> {
+    fn find(cdb: &'a CDB<S, H>, key: &[u8]) -> Self {
+        cdb.find_with_hash(key, cdb.hasher.hash(key))
+    }
+}
+
+impl<'a, S: Storage, H: CDBHash> CDBValueIter<'a, S, H> {
+    /// Read the next value into the given buffer, reusing its
+    /// allocation. Returns `false` when there are no more values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "std")] {
+    /// let cdb = cdb::CDB::open_pread("tests/test1.cdb").unwrap();
+    /// let mut values = cdb.find(b"one");
+    /// let mut value = Vec::new();
+    /// while values.next_into(&mut value).unwrap() {
+    ///     println!("{:?}", value);
+    /// }
+    /// # }
+    /// ```
+    pub fn next_into(&mut self, buf: &mut Vec<u8>) -> Result<bool> {
+        match self.lookup.next(self.cdb, &self.key) {
+            None => Ok(false),
+            Some(Err(err)) => Err(err),
+            Some(Ok((dpos, dlen))) => self.cdb.read_into(dpos, dlen, buf).map(|()| true),
+        }
+    }
+
+    /// Returns a reader for the next value, instead of a copy of it.
+    ///
+    /// See [`CDB::get_reader`](struct.CDB.html#method.get_reader)
+    #[cfg(feature = "std")]
+    pub fn next_reader(&mut self) -> Option<Result<ValueReader<'a, S, H>>> {
+        let (dpos, dlen) = iter_try!(self.lookup.next(self.cdb, &self.key)?);
+        Some(ValueReader::new(self.cdb, dpos, dlen))
+    }
 }
 
-impl<'a> CDBKeyValueIter<'a> {
-    fn start(cdb: &'a CDB) -> Self {
-        let data_end = uint32::unpack(&cdb.file[0..4]).min(cdb.size as u32);
-        Self {
+impl<S: Storage, H: CDBHash> Iterator for CDBValueIter<'_, S, H> {
+    type Item = Result<Vec<u8>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let (dpos, dlen) = iter_try!(self.lookup.next(self.cdb, &self.key)?);
+        Some(self.cdb.read_vec(dpos, dlen))
+    }
+}
+
+/// Iterator over the lengths of a set of values in the CDB with the
+/// same key.
+///
+/// See [`CDB::value_lens`](struct.CDB.html#method.value_lens)
+pub struct CDBValueLenIter<'a, S = &'static [u8], H = DjbHash> {
+    cdb: &'a CDB<S, H>,
+    key: Vec<u8>,
+    lookup: Lookup,
+}
+
+impl<S: Storage, H: CDBHash> Iterator for CDBValueLenIter<'_, S, H> {
+    type Item = Result<u64>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let (_, dlen) = iter_try!(self.lookup.next(self.cdb, &self.key)?);
+        Some(Ok(dlen))
+    }
+}
+
+enum OffsetState {
+    Find(Vec<u8>, Lookup),
+    Scan(Scan),
+}
+
+/// Iterator over the offsets of records in the CDB.
+///
+/// See [`CDB::find_offsets`](struct.CDB.html#method.find_offsets) and
+/// [`CDB::iter_offsets`](struct.CDB.html#method.iter_offsets)
+pub struct CDBOffsetIter<'a, S = &'static [u8], H = DjbHash> {
+    cdb: &'a CDB<S, H>,
+    state: OffsetState,
+}
+
+impl<S: Storage, H: CDBHash> Iterator for CDBOffsetIter<'_, S, H> {
+    type Item = Result<u64>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let pair_size = self.cdb.format.pair_size();
+        match &mut self.state {
+            OffsetState::Find(key, lookup) => {
+                let (dpos, _) = iter_try!(lookup.next(self.cdb, key)?);
+                Some(Ok(dpos - key.len() as u64 - pair_size))
+            }
+            OffsetState::Scan(scan) => {
+                let (kpos, _, _) = iter_try!(scan.next(self.cdb)?);
+                Some(Ok(kpos - pair_size))
+            }
+        }
+    }
+}
+
+/// Iterator over a set of records in the CDB with the same key,
+/// producing slices borrowed from the underlying file.
+///
+/// See [`CDB::find_ref`](struct.CDB.html#method.find_ref)
+pub struct CDBValueRefIter<'a, 'k, S = &'static [u8], H = DjbHash> {
+    cdb: &'a CDB<S, H>,
+    key: &'k [u8],
+    lookup: Lookup,
+}
+
+impl<'a, 'k, S: SliceStorage, H: CDBHash> CDBValueRefIter<'a, 'k, S, H> {
+    fn find(cdb: &'a CDB<S, H>, key: &'k [u8]) -> Self {
+        CDBValueRefIter {
             cdb,
-            pos: 2048,
-            data_end,
+            key,
+            lookup: Lookup::new(cdb.hasher.hash(key)),
         }
     }
 }
 
-impl<'a> Iterator for CDBKeyValueIter<'a> {
-    type Item = Result<(Vec<u8>, Vec<u8>)>;
+impl<'a, S: SliceStorage, H: CDBHash> Iterator for CDBValueRefIter<'a, '_, S, H> {
+    type Item = Result<&'a [u8]>;
     fn next(&mut self) -> Option<Self::Item> {
-        if self.pos + 8 >= self.data_end {
-            None
-        } else {
-            let (klen, dlen) =
-                uint32::unpack2(&self.cdb.file[self.pos as usize..self.pos as usize + 8]);
-            if self.pos + klen + dlen >= self.data_end {
-                Some(err_badfile())
-            } else {
-                let kpos = (self.pos + 8) as usize;
-                let dpos = kpos + klen as usize;
-                let mut key = vec![0; klen as usize];
-                let mut value = vec![0; dlen as usize];
-                // Copied from CDB::read
-                key.copy_from_slice(&self.cdb.file[kpos..kpos + klen as usize]);
-                value.copy_from_slice(&self.cdb.file[dpos..dpos + dlen as usize]);
-                self.pos += 8 + klen + dlen;
-                Some(Ok((key, value)))
+        let (dpos, dlen) = iter_try!(self.lookup.next(self.cdb, self.key)?);
+        Some(self.cdb.slice(dpos, dlen))
+    }
+}
+
+/// A position in a scan through all the records of a CDB, from which
+/// the scan can later be resumed.
+///
+/// A cursor is the offset in the file of the next record to be read,
+/// which can be saved and restored with
+/// [`offset`](#method.offset) and [`new`](#method.new).
+///
+/// See [`CDB::iter_from`](struct.CDB.html#method.iter_from)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Cursor {
+    pos: u64,
+}
+
+impl Cursor {
+    /// Create a cursor from an offset previously returned by
+    /// [`offset`](#method.offset).
+    pub fn new(offset: u64) -> Cursor {
+        Cursor { pos: offset }
+    }
+
+    /// The offset in the file of the next record to be read.
+    pub fn offset(&self) -> u64 {
+        self.pos
+    }
+}
+
+/// The state of a sequential scan through all the records.
+pub(crate) struct Scan {
+    pos: u64,
+    data_end: u64,
+}
+
+impl Scan {
+    pub(crate) fn new() -> Self {
+        Scan {
+            pos: 0,
+            data_end: 0,
+        }
+    }
+
+    /// Scan the records between two record boundaries.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn range(start: u64, end: u64) -> Self {
+        Scan {
+            pos: start,
+            data_end: end,
+        }
+    }
+
+    /// Start a scan at the given cursor, after checking that it points
+    /// at a record boundary.
+    pub(crate) fn at<S: Storage, H: CDBHash>(cdb: &CDB<S, H>, cursor: Cursor) -> Result<Self> {
+        let data_end = Scan::data_end(cdb)?;
+        let pos = cursor.pos;
+        if pos != cdb.format.header_size() && pos != data_end && !cdb.is_record(pos, data_end)? {
+            return Err(Error::InvalidCursor { offset: pos });
+        }
+        Ok(Scan { pos, data_end })
+    }
+
+    pub(crate) fn data_end<S: Storage, H: CDBHash>(cdb: &CDB<S, H>) -> Result<u64> {
+        let (data_end, _) = cdb.read_pair(0)?;
+        Ok(data_end.min(cdb.size))
+    }
+
+    /// The cursor for the next record to be read.
+    pub(crate) fn cursor<S: Storage, H: CDBHash>(&self, cdb: &CDB<S, H>) -> Cursor {
+        match self.pos {
+            0 => Cursor::new(cdb.format.header_size()),
+            pos => Cursor::new(pos),
+        }
+    }
+
+    /// Find the next record, returning the position of its key and
+    /// the lengths of its key and value. The value follows the key.
+    ///
+    /// The scan ends at the first error, so that a corrupt file cannot
+    /// produce the same error forever. Its cursor is left at the
+    /// record which could not be read.
+    pub(crate) fn next<S: Storage, H: CDBHash>(
+        &mut self,
+        cdb: &CDB<S, H>,
+    ) -> Option<Result<(u64, u64, u64)>> {
+        let found = self.step(cdb).transpose();
+        if let Some(Err(_)) = found {
+            self.pos = self.pos.max(cdb.format.header_size());
+            self.data_end = 0;
+        }
+        found
+    }
+
+    fn step<S: Storage, H: CDBHash>(&mut self, cdb: &CDB<S, H>) -> Result<Option<(u64, u64, u64)>> {
+        let pair_size = cdb.format.pair_size();
+        if self.pos == 0 {
+            self.data_end = Scan::data_end(cdb)?;
+            self.pos = cdb.format.header_size();
+        }
+        if self.pos >= self.data_end {
+            return Ok(None);
+        }
+        if self.data_end - self.pos < pair_size {
+            return Err(Error::RecordPastEnd { offset: self.pos });
+        }
+        let (klen, dlen) = cdb.read_pair(self.pos)?;
+        let kpos = self.pos + pair_size;
+        match kpos.checked_add(klen).and_then(|end| end.checked_add(dlen)) {
+            Some(end) if end <= self.data_end => {
+                self.pos = end;
+                Ok(Some((kpos, klen, dlen)))
             }
+            _ => Err(Error::RecordPastEnd { offset: self.pos }),
         }
     }
 }
+
+/// Iterator over all the records in the CDB.
+///
+/// See [`CDB::iter`](struct.CDB.html#method.iter)
+pub struct CDBKeyValueIter<'a, S = &'static [u8], H = DjbHash> {
+    cdb: &'a CDB<S, H>,
+    scan: Scan,
+}
+
+impl<'a, S: Storage, H: CDBHash> CDBKeyValueIter<'a, S, H> {
+    fn start(cdb: &'a CDB<S, H>) -> Self {
+        Self::with_scan(cdb, Scan::new())
+    }
+
+    pub(crate) fn with_scan(cdb: &'a CDB<S, H>, scan: Scan) -> Self {
+        Self { cdb, scan }
+    }
+}
+
+impl<S: Storage, H: CDBHash> CDBKeyValueIter<'_, S, H> {
+    /// Returns a cursor for the next record to be produced, from which
+    /// iteration can be resumed with
+    /// [`CDB::iter_from`](struct.CDB.html#method.iter_from).
+    pub fn cursor(&self) -> Cursor {
+        self.scan.cursor(self.cdb)
+    }
+}
+
+impl<S: Storage, H: CDBHash> Iterator for CDBKeyValueIter<'_, S, H> {
+    type Item = Result<(Vec<u8>, Vec<u8>)>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let (kpos, klen, dlen) = iter_try!(self.scan.next(self.cdb)?);
+        let key = iter_try!(self.cdb.read_vec(kpos, klen));
+        let value = iter_try!(self.cdb.read_vec(kpos + klen, dlen));
+        Some(Ok((key, value)))
+    }
+}
+
+/// Iterator over all the records in the CDB, producing slices
+/// borrowed from the underlying file.
+///
+/// See [`CDB::iter_ref`](struct.CDB.html#method.iter_ref)
+pub struct CDBKeyValueRefIter<'a, S = &'static [u8], H = DjbHash> {
+    cdb: &'a CDB<S, H>,
+    scan: Scan,
+}
+
+impl<'a, S: SliceStorage, H: CDBHash> CDBKeyValueRefIter<'a, S, H> {
+    fn start(cdb: &'a CDB<S, H>) -> Self {
+        Self::with_scan(cdb, Scan::new())
+    }
+
+    pub(crate) fn with_scan(cdb: &'a CDB<S, H>, scan: Scan) -> Self {
+        Self { cdb, scan }
+    }
+}
+
+impl<S: SliceStorage, H: CDBHash> CDBKeyValueRefIter<'_, S, H> {
+    /// Returns a cursor for the next record to be produced, from which
+    /// iteration can be resumed with
+    /// [`CDB::iter_ref_from`](struct.CDB.html#method.iter_ref_from).
+    pub fn cursor(&self) -> Cursor {
+        self.scan.cursor(self.cdb)
+    }
+}
+
+impl<'a, S: SliceStorage, H: CDBHash> Iterator for CDBKeyValueRefIter<'a, S, H> {
+    type Item = Result<(&'a [u8], &'a [u8])>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let (kpos, klen, dlen) = iter_try!(self.scan.next(self.cdb)?);
+        let key = iter_try!(self.cdb.slice(kpos, klen));
+        let value = iter_try!(self.cdb.slice(kpos + klen, dlen));
+        Some(Ok((key, value)))
+    }
+}
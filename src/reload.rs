@@ -0,0 +1,128 @@
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::SystemTime;
+
+use crate::mmap::{MapOptions, MappedFile};
+use crate::reader::{CDB, Result};
+
+/// The identity of a file, used to tell when it has been replaced.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Stamp {
+    dev: u64,
+    ino: u64,
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl Stamp {
+    fn of(meta: &fs::Metadata) -> Stamp {
+        Stamp {
+            dev: meta.dev(),
+            ino: meta.ino(),
+            len: meta.len(),
+            modified: meta.modified().ok(),
+        }
+    }
+}
+
+/// Open the named file, returning a reader for it along with the stamp
+/// of the file that was actually opened.
+fn load(filename: &path::Path) -> Result<(Stamp, CDB<MappedFile>)> {
+    let file = fs::File::open(filename)?;
+    let stamp = Stamp::of(&file.metadata()?);
+    // Map the file that was stamped rather than opening the path again,
+    // as the path may have been replaced in the meantime.
+    let cdb = CDB::new(MapOptions::new().map_file(&file)?)?;
+    Ok((stamp, cdb))
+}
+
+/// A CDB reader that follows atomic replacement of its file.
+///
+/// [`CDBWriter`](struct.CDBWriter.html) publishes a new database by
+/// renaming it over the old one, but an open [`CDB`](struct.CDB.html)
+/// keeps reading the file it opened. This handle notices when the file
+/// at its path has been replaced, by comparing its device, inode,
+/// size and modification time, and swaps in a reader for the new file.
+///
+/// Lookups are done on a snapshot returned by
+/// [`cdb`](#method.cdb). A snapshot keeps reading the file it was
+/// taken from, so lookups in progress during a reload finish against
+/// the old file, which is unmapped once the last snapshot is dropped.
+///
+/// The file is polled for changes by calling
+/// [`reload`](#method.reload), for example from a timer or a signal
+/// handler thread.
+///
+/// This type is only available on Unix.
+///
+/// # Example
+///
+/// ```
+/// let db = cdb::ReloadingCDB::open("tests/test1.cdb").unwrap();
+/// assert_eq!(db.cdb().get(b"two").unwrap().unwrap(), b"Goodbye");
+/// // Some time later:
+/// if db.reload().unwrap() {
+///     println!("reloaded");
+/// }
+/// ```
+pub struct ReloadingCDB {
+    filename: path::PathBuf,
+    current: RwLock<(Stamp, Arc<CDB<MappedFile>>)>,
+    /// Held while reloading, so that a replacement is only loaded once.
+    reloading: Mutex<()>,
+}
+
+impl ReloadingCDB {
+    /// Opens the named file and returns the reloading reader.
+    pub fn open<P: AsRef<path::Path>>(filename: P) -> Result<ReloadingCDB> {
+        let filename = filename.as_ref().to_path_buf();
+        let (stamp, cdb) = load(&filename)?;
+        Ok(ReloadingCDB {
+            filename,
+            current: RwLock::new((stamp, Arc::new(cdb))),
+            reloading: Mutex::new(()),
+        })
+    }
+
+    /// Returns the path of the file being followed.
+    pub fn path(&self) -> &path::Path {
+        &self.filename
+    }
+
+    /// Returns a snapshot of the current database.
+    pub fn cdb(&self) -> Arc<CDB<MappedFile>> {
+        // A poisoned lock still holds a valid reader, as it is only
+        // ever replaced whole.
+        let current = self.current.read().unwrap_or_else(|e| e.into_inner());
+        Arc::clone(&current.1)
+    }
+
+    /// Check whether the file has been replaced, and if it has, open
+    /// the new file and make it current. Returns `true` if a new file
+    /// was loaded.
+    ///
+    /// If the new file cannot be opened, the error is returned and the
+    /// previous database remains current. Calls are serialized, so
+    /// when several notice the same replacement at once, only the first
+    /// loads it.
+    pub fn reload(&self) -> Result<bool> {
+        let _reloading = self.reloading.lock().unwrap_or_else(|e| e.into_inner());
+        let seen = self.stamp();
+        if Stamp::of(&fs::metadata(&self.filename)?) == seen {
+            return Ok(false);
+        }
+        let (stamp, cdb) = load(&self.filename)?;
+        if stamp == seen {
+            return Ok(false);
+        }
+        let mut current = self.current.write().unwrap_or_else(|e| e.into_inner());
+        *current = (stamp, Arc::new(cdb));
+        Ok(true)
+    }
+
+    fn stamp(&self) -> Stamp {
+        self.current.read().unwrap_or_else(|e| e.into_inner()).0
+    }
+}
@@ -0,0 +1,211 @@
+use filebuffer::FileBuffer;
+use std::fmt;
+use std::ops::{Deref, Range};
+use std::path;
+use std::sync::Arc;
+
+use crate::hash::{CDBHash, DjbHash};
+use crate::reader::{CDB, Lookup, Result, Scan};
+use crate::storage::SliceStorage;
+
+/// A cloneable, thread-safe handle to a CDB reader.
+///
+/// Lookups return [`CDBBytes`](struct.CDBBytes.html), which are
+/// reference-counted views into the underlying storage rather than
+/// copies. Neither the views nor the iterators borrow the handle, so
+/// they can be stored in caches or moved into other threads, keeping
+/// the database open for as long as any of them exist.
+///
+/// # Example
+///
+/// ```
+/// let cdb = cdb::SharedCDB::open("tests/test1.cdb").unwrap();
+/// let value = cdb.get(b"two").unwrap().unwrap();
+/// std::thread::spawn(move || {
+///     assert_eq!(&value[..], b"Goodbye");
+/// })
+/// .join()
+/// .unwrap();
+/// ```
+pub struct SharedCDB<S = FileBuffer, H = DjbHash> {
+    cdb: Arc<CDB<S, H>>,
+}
+
+impl<S, H> Clone for SharedCDB<S, H> {
+    fn clone(&self) -> Self {
+        SharedCDB {
+            cdb: Arc::clone(&self.cdb),
+        }
+    }
+}
+
+impl<S, H> From<Arc<CDB<S, H>>> for SharedCDB<S, H> {
+    fn from(cdb: Arc<CDB<S, H>>) -> Self {
+        SharedCDB { cdb }
+    }
+}
+
+impl SharedCDB {
+    /// Opens the named file and returns the shared CDB reader.
+    pub fn open<P: AsRef<path::Path>>(filename: P) -> Result<SharedCDB> {
+        Ok(SharedCDB::new(CDB::open(filename)?))
+    }
+}
+
+impl<S: SliceStorage, H: CDBHash> SharedCDB<S, H> {
+    /// Wraps an already opened CDB reader.
+    pub fn new(cdb: CDB<S, H>) -> SharedCDB<S, H> {
+        SharedCDB { cdb: Arc::new(cdb) }
+    }
+
+    /// Returns the underlying CDB reader.
+    pub fn cdb(&self) -> &Arc<CDB<S, H>> {
+        &self.cdb
+    }
+
+    /// Find the first record with the named key.
+    pub fn get(&self, key: &[u8]) -> Option<Result<CDBBytes<S, H>>> {
+        self.find(key).next()
+    }
+
+    /// Find all records with the named key. The returned iterator
+    /// produces each value associated with the key.
+    pub fn find(&self, key: &[u8]) -> SharedValueIter<S, H> {
+        SharedValueIter {
+            cdb: Arc::clone(&self.cdb),
+            key: key.to_vec(),
+            lookup: Lookup::new(self.cdb.hasher().hash(key)),
+        }
+    }
+
+    /// Iterate over all the `(key, value)` pairs in the database.
+    pub fn iter(&self) -> SharedKeyValueIter<S, H> {
+        SharedKeyValueIter {
+            cdb: Arc::clone(&self.cdb),
+            scan: Scan::new(),
+        }
+    }
+}
+
+/// A reference-counted view of a key or value in a
+/// [`SharedCDB`](struct.SharedCDB.html).
+///
+/// Cloning the view only increments the reference count on the
+/// database.
+pub struct CDBBytes<S = FileBuffer, H = DjbHash> {
+    cdb: Arc<CDB<S, H>>,
+    range: Range<usize>,
+}
+
+impl<S: SliceStorage, H: CDBHash> CDBBytes<S, H> {
+    fn new(cdb: &Arc<CDB<S, H>>, pos: u64, len: u64) -> Result<Self> {
+        // Check the bounds once, so that deref() cannot fail.
+        cdb.slice(pos, len)?;
+        Ok(CDBBytes {
+            cdb: Arc::clone(cdb),
+            range: pos as usize..(pos + len) as usize,
+        })
+    }
+
+    /// Returns a view of a subrange of these bytes, sharing the same
+    /// database.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds of these bytes.
+    pub fn slice(&self, range: Range<usize>) -> Self {
+        assert!(range.start <= range.end && range.end <= self.range.len());
+        CDBBytes {
+            cdb: Arc::clone(&self.cdb),
+            range: self.range.start + range.start..self.range.start + range.end,
+        }
+    }
+}
+
+impl<S, H> Clone for CDBBytes<S, H> {
+    fn clone(&self) -> Self {
+        CDBBytes {
+            cdb: Arc::clone(&self.cdb),
+            range: self.range.clone(),
+        }
+    }
+}
+
+impl<S: SliceStorage, H: CDBHash> Deref for CDBBytes<S, H> {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] {
+        &self.cdb.storage().as_bytes()[self.range.clone()]
+    }
+}
+
+impl<S: SliceStorage, H: CDBHash> AsRef<[u8]> for CDBBytes<S, H> {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl<S: SliceStorage, H: CDBHash> PartialEq for CDBBytes<S, H> {
+    fn eq(&self, other: &Self) -> bool {
+        self[..] == other[..]
+    }
+}
+
+impl<S: SliceStorage, H: CDBHash> Eq for CDBBytes<S, H> {}
+
+impl<S: SliceStorage, H: CDBHash> PartialEq<[u8]> for CDBBytes<S, H> {
+    fn eq(&self, other: &[u8]) -> bool {
+        self[..] == *other
+    }
+}
+
+impl<S: SliceStorage, H: CDBHash, const N: usize> PartialEq<[u8; N]> for CDBBytes<S, H> {
+    fn eq(&self, other: &[u8; N]) -> bool {
+        self[..] == other[..]
+    }
+}
+
+impl<S: SliceStorage, H: CDBHash> fmt::Debug for CDBBytes<S, H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self[..], f)
+    }
+}
+
+/// Owning iterator over a set of records in the CDB with the same key.
+///
+/// See [`SharedCDB::find`](struct.SharedCDB.html#method.find)
+pub struct SharedValueIter<S = FileBuffer, H = DjbHash> {
+    cdb: Arc<CDB<S, H>>,
+    key: Vec<u8>,
+    lookup: Lookup,
+}
+
+impl<S: SliceStorage, H: CDBHash> Iterator for SharedValueIter<S, H> {
+    type Item = Result<CDBBytes<S, H>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(match self.lookup.next(&self.cdb, &self.key)? {
+            Ok((dpos, dlen)) => CDBBytes::new(&self.cdb, dpos, dlen),
+            Err(err) => Err(err),
+        })
+    }
+}
+
+/// Owning iterator over all the records in the CDB.
+///
+/// See [`SharedCDB::iter`](struct.SharedCDB.html#method.iter)
+pub struct SharedKeyValueIter<S = FileBuffer, H = DjbHash> {
+    cdb: Arc<CDB<S, H>>,
+    scan: Scan,
+}
+
+impl<S: SliceStorage, H: CDBHash> Iterator for SharedKeyValueIter<S, H> {
+    type Item = Result<(CDBBytes<S, H>, CDBBytes<S, H>)>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let (kpos, klen, dlen) = match self.scan.next(&self.cdb)? {
+            Ok(found) => found,
+            Err(err) => return Some(Err(err)),
+        };
+        let key = CDBBytes::new(&self.cdb, kpos, klen);
+        let value = CDBBytes::new(&self.cdb, kpos + klen, dlen);
+        Some(key.and_then(|key| value.map(|value| (key, value))))
+    }
+}
@@ -0,0 +1,150 @@
+use alloc::collections::BinaryHeap;
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+
+use crate::hash::CDBHash;
+use crate::reader::{CDB, Lookup, Result, Scan};
+use crate::storage::Storage;
+
+/// The number of records listed in [`Stats::largest`](struct.Stats.html#structfield.largest).
+const LARGEST: usize = 10;
+
+/// Statistics about the contents and layout of a CDB.
+///
+/// See [`CDB::stats`](struct.CDB.html#method.stats)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// The number of records.
+    pub records: u64,
+    /// The number of distinct keys.
+    pub distinct_keys: u64,
+    /// The number of distinct keys which have more than one value.
+    pub multi_value_keys: u64,
+    /// The total length of all the keys.
+    pub key_bytes: u64,
+    /// The total length of all the values.
+    pub value_bytes: u64,
+    /// The slot usage of each of the 256 hash tables.
+    pub tables: Vec<TableStats>,
+    /// A histogram of the distance of each record's slot from the
+    /// first slot probed for its hash. A lookup for a record at
+    /// distance `d` reads `d + 1` slots. Entry `d` holds the number of
+    /// records at that distance.
+    pub probes: Vec<u64>,
+    /// The largest records by combined key and value length, largest
+    /// first.
+    pub largest: Vec<RecordSize>,
+}
+
+/// The slot usage of one hash table.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TableStats {
+    /// The number of slots in the table.
+    pub slots: u64,
+    /// The number of slots pointing at records.
+    pub used: u64,
+}
+
+impl TableStats {
+    /// The fraction of the slots which are in use.
+    pub fn load_factor(&self) -> f64 {
+        match self.slots {
+            0 => 0.0,
+            slots => self.used as f64 / slots as f64,
+        }
+    }
+}
+
+/// The size of one record.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RecordSize {
+    /// The offset of the record in the file.
+    pub offset: u64,
+    /// The length of the key.
+    pub key_len: u64,
+    /// The length of the value.
+    pub value_len: u64,
+}
+
+impl<S: Storage, H: CDBHash> CDB<S, H> {
+    /// Gather statistics about the database, in the manner of
+    /// `cdbstats`.
+    ///
+    /// This reads the whole file and looks up every key, so it takes
+    /// time proportional to its size. The ten largest records are
+    /// listed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "std")] {
+    /// let cdb = cdb::CDB::open("tests/test1.cdb").unwrap();
+    /// let stats = cdb.stats().unwrap();
+    /// println!("{} records, {} keys", stats.records, stats.distinct_keys);
+    /// for (distance, count) in stats.probes.iter().enumerate() {
+    ///     println!("{}: {}", distance, count);
+    /// }
+    /// # }
+    /// ```
+    pub fn stats(&self) -> Result<Stats> {
+        let pair_size = self.format().pair_size();
+        let mut stats = Stats::default();
+        let mut largest = BinaryHeap::with_capacity(LARGEST + 1);
+
+        let mut scan = Scan::new();
+        while let Some(found) = scan.next(self) {
+            let (kpos, klen, dlen) = found?;
+            stats.records += 1;
+            stats.key_bytes += klen;
+            stats.value_bytes += dlen;
+
+            // A key is counted at its first record found by a lookup.
+            let key = self.read_vec(kpos, klen)?;
+            let mut lookup = Lookup::new(self.hasher().hash(&key));
+            let first = lookup.next(self, &key).transpose()?;
+            if first.is_some_and(|(dpos, _)| dpos == kpos + klen) {
+                stats.distinct_keys += 1;
+                if lookup.next(self, &key).transpose()?.is_some() {
+                    stats.multi_value_keys += 1;
+                }
+            }
+
+            largest.push(Reverse((klen + dlen, kpos - pair_size, klen, dlen)));
+            if largest.len() > LARGEST {
+                largest.pop();
+            }
+        }
+        stats.largest = largest
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((_, offset, key_len, value_len))| RecordSize {
+                offset,
+                key_len,
+                value_len,
+            })
+            .collect();
+
+        for i in 0..256 {
+            let (hpos, hslots) = self.read_table(i)?;
+            let mut table = TableStats {
+                slots: hslots,
+                used: 0,
+            };
+            for slot in 0..hslots {
+                let (hash, pos) = self.read_pair(hpos + slot * pair_size)?;
+                if pos == 0 {
+                    continue;
+                }
+                table.used += 1;
+                let home = (hash >> 8) % hslots;
+                let distance = ((slot + hslots - home) % hslots) as usize;
+                if stats.probes.len() <= distance {
+                    stats.probes.resize(distance + 1, 0);
+                }
+                stats.probes[distance] += 1;
+            }
+            stats.tables.push(table);
+        }
+        Ok(stats)
+    }
+}
@@ -0,0 +1,184 @@
+#[cfg(feature = "std")]
+use std::fs;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::path;
+
+use crate::error::{Error, Result};
+
+/// Backing storage for a [`CDB`](struct.CDB.html) reader.
+///
+/// A storage provides positional reads from a fixed-size region of
+/// bytes. Storages that hold the whole database in addressable memory
+/// should also implement [`SliceStorage`](trait.SliceStorage.html),
+/// which enables the zero-copy lookup methods.
+///
+/// This trait is implemented for every type that implements
+/// `AsRef<[u8]>`, which includes memory mapped files
+/// (`filebuffer::FileBuffer`) as well as in-memory buffers such as
+/// `&'static [u8]`, `Vec<u8>` and `Box<[u8]>`. Files accessed with
+/// positional reads are provided by [`PreadFile`](struct.PreadFile.html).
+pub trait Storage {
+    /// The total size of the storage in bytes.
+    fn size(&self) -> u64;
+
+    /// Fill the buffer with the bytes starting at `pos`.
+    ///
+    /// It is an error for the requested range to extend past the end
+    /// of the storage, which should be reported as
+    /// [`Error::Truncated`](enum.Error.html#variant.Truncated).
+    fn read_at(&self, buf: &mut [u8], pos: u64) -> Result<()>;
+
+    /// Hint that the bytes starting at `pos` will be read soon.
+    ///
+    /// This is only advice, which may start reading the bytes in the
+    /// background. The default implementation does nothing.
+    fn prefetch(&self, pos: u64, len: u64) {
+        let _ = (pos, len);
+    }
+}
+
+/// Storage which can provide direct access to its bytes.
+pub trait SliceStorage: Storage {
+    /// Returns all the bytes of the storage.
+    fn as_bytes(&self) -> &[u8];
+}
+
+impl<T: AsRef<[u8]>> Storage for T {
+    fn size(&self) -> u64 {
+        self.as_ref().len() as u64
+    }
+
+    fn read_at(&self, buf: &mut [u8], pos: u64) -> Result<()> {
+        let data = self.as_ref();
+        let start = pos as usize;
+        match start.checked_add(buf.len()) {
+            Some(end) if pos <= usize::MAX as u64 && end <= data.len() => {
+                buf.copy_from_slice(&data[start..end]);
+                Ok(())
+            }
+            _ => Err(Error::Truncated),
+        }
+    }
+
+    #[cfg(all(unix, feature = "std"))]
+    fn prefetch(&self, pos: u64, len: u64) {
+        if let Some((addr, len)) = page_range(self.as_ref(), pos, len) {
+            // SAFETY: the advice only covers pages that contain bytes of
+            // the slice, and MADV_WILLNEED never changes their contents.
+            // Failures are ignored, as the advice is optional.
+            unsafe { libc::madvise(addr, len, libc::MADV_WILLNEED) };
+        }
+    }
+}
+
+#[cfg(all(unix, feature = "std"))]
+pub(crate) fn page_size() -> usize {
+    use std::sync::OnceLock;
+    static PAGE_SIZE: OnceLock<usize> = OnceLock::new();
+    // SAFETY: sysconf has no preconditions.
+    *PAGE_SIZE.get_or_init(|| unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize)
+}
+
+/// Round a byte range in memory out to whole pages.
+#[cfg(all(unix, feature = "std"))]
+pub(crate) fn page_range(data: &[u8], pos: u64, len: u64) -> Option<(*mut libc::c_void, usize)> {
+    if pos >= data.len() as u64 || len == 0 {
+        return None;
+    }
+    let len = len.min(data.len() as u64 - pos) as usize;
+    let start = data.as_ptr() as usize + pos as usize;
+    let aligned = start & !(page_size() - 1);
+    Some((aligned as *mut libc::c_void, len + (start - aligned)))
+}
+
+impl<T: AsRef<[u8]>> SliceStorage for T {
+    fn as_bytes(&self) -> &[u8] {
+        self.as_ref()
+    }
+}
+
+/// Storage that reads from a file using positional reads (`pread`)
+/// instead of memory mapping it.
+///
+/// # Example
+///
+/// ```
+/// let file = cdb::PreadFile::open("tests/test1.cdb").unwrap();
+/// let cdb = cdb::CDB::new(file).unwrap();
+/// assert_eq!(cdb.get(b"two").unwrap().unwrap(), b"Goodbye");
+/// ```
+///
+/// This type is only available with the `std` feature.
+#[cfg(feature = "std")]
+pub struct PreadFile {
+    file: fs::File,
+    size: u64,
+}
+
+#[cfg(feature = "std")]
+impl PreadFile {
+    /// Opens the named file for positional reads.
+    pub fn open<P: AsRef<path::Path>>(filename: P) -> io::Result<PreadFile> {
+        PreadFile::new(fs::File::open(filename)?)
+    }
+
+    /// Wraps an already opened file. The size of the storage is fixed
+    /// to the size of the file at this point.
+    pub fn new(file: fs::File) -> io::Result<PreadFile> {
+        let size = file.metadata()?.len();
+        Ok(PreadFile { file, size })
+    }
+}
+
+#[cfg(feature = "std")]
+impl Storage for PreadFile {
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    #[cfg(unix)]
+    fn read_at(&self, buf: &mut [u8], pos: u64) -> Result<()> {
+        use std::os::unix::fs::FileExt;
+        self.file
+            .read_exact_at(buf, pos)
+            .map_err(|err| match err.kind() {
+                io::ErrorKind::UnexpectedEof => Error::Truncated,
+                _ => Error::Io(err),
+            })
+    }
+
+    #[cfg(windows)]
+    fn read_at(&self, mut buf: &mut [u8], mut pos: u64) -> Result<()> {
+        use std::os::windows::fs::FileExt;
+        while !buf.is_empty() {
+            match self.file.seek_read(buf, pos) {
+                Ok(0) => return Err(Error::Truncated),
+                Ok(n) => {
+                    buf = &mut buf[n..];
+                    pos += n as u64;
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(Error::Io(e)),
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn prefetch(&self, pos: u64, len: u64) {
+        use std::os::unix::io::AsRawFd;
+        // SAFETY: posix_fadvise only takes plain values, and the file
+        // descriptor stays open for the duration of the call. Failures
+        // are ignored, as the advice is optional.
+        unsafe {
+            libc::posix_fadvise(
+                self.file.as_raw_fd(),
+                pos as libc::off_t,
+                len as libc::off_t,
+                libc::POSIX_FADV_WILLNEED,
+            );
+        }
+    }
+}
@@ -1,5 +1,4 @@
-use std::convert::TryInto;
-use std::u32;
+use core::convert::TryInto;
 
 pub fn unpack(data: &[u8]) -> u32 {
     u32::from_le_bytes(data[0..4].try_into().unwrap())
@@ -13,11 +12,13 @@ fn _pack(src: u32) -> [u8; 4] {
     src.to_le_bytes()
 }
 
+#[cfg(feature = "std")]
 pub fn pack(data: &mut [u8], src: u32) {
     assert!(data.len() >= 4);
     data[..4].copy_from_slice(&_pack(src));
 }
 
+#[cfg(feature = "std")]
 pub fn pack2(data: &mut [u8], src0: u32, src1: u32) {
     assert!(data.len() >= 8);
     pack(&mut data[0..4], src0);
@@ -0,0 +1,22 @@
+use core::convert::TryInto;
+
+pub fn unpack(data: &[u8]) -> u64 {
+    u64::from_le_bytes(data[0..8].try_into().unwrap())
+}
+
+pub fn unpack2(buf: &[u8]) -> (u64, u64) {
+    (unpack(&buf[0..8]), unpack(&buf[8..16]))
+}
+
+#[cfg(feature = "std")]
+pub fn pack(data: &mut [u8], src: u64) {
+    assert!(data.len() >= 8);
+    data[..8].copy_from_slice(&src.to_le_bytes());
+}
+
+#[cfg(feature = "std")]
+pub fn pack2(data: &mut [u8], src0: u64, src1: u64) {
+    assert!(data.len() >= 16);
+    pack(&mut data[0..8], src0);
+    pack(&mut data[8..16], src1);
+}
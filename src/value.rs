@@ -0,0 +1,107 @@
+use filebuffer::FileBuffer;
+use std::io;
+
+use crate::error::Error;
+use crate::hash::{CDBHash, DjbHash};
+use crate::reader::{CDB, Lookup, Result};
+use crate::storage::Storage;
+
+/// A reader over a single value in a CDB, which reads the value in
+/// pieces as it is needed instead of all at once.
+///
+/// See [`CDB::get_reader`](struct.CDB.html#method.get_reader)
+pub struct ValueReader<'a, S = FileBuffer, H = DjbHash> {
+    cdb: &'a CDB<S, H>,
+    start: u64,
+    len: u64,
+    pos: u64,
+}
+
+impl<'a, S: Storage, H: CDBHash> ValueReader<'a, S, H> {
+    pub(crate) fn new(cdb: &'a CDB<S, H>, start: u64, len: u64) -> Result<Self> {
+        // Check the length up front, so that a corrupt length is
+        // reported here instead of part way through reading.
+        match start.checked_add(len) {
+            Some(end) if end <= cdb.storage().size() => Ok(ValueReader {
+                cdb,
+                start,
+                len,
+                pos: 0,
+            }),
+            _ => Err(Error::OutOfRange { offset: start }),
+        }
+    }
+
+    /// The length of the value in bytes.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns true if the value is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The offset of the value in the file.
+    pub fn offset(&self) -> u64 {
+        self.start
+    }
+}
+
+impl<S: Storage, H: CDBHash> io::Read for ValueReader<'_, S, H> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.len.saturating_sub(self.pos);
+        let n = (buf.len() as u64).min(remaining) as usize;
+        if n > 0 {
+            self.cdb
+                .storage()
+                .read_at(&mut buf[..n], self.start + self.pos)?;
+            self.pos += n as u64;
+        }
+        Ok(n)
+    }
+}
+
+impl<S: Storage, H: CDBHash> io::Seek for ValueReader<'_, S, H> {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            io::SeekFrom::Start(pos) => Some(pos),
+            io::SeekFrom::End(offset) => self.len.checked_add_signed(offset),
+            io::SeekFrom::Current(offset) => self.pos.checked_add_signed(offset),
+        };
+        match pos {
+            Some(pos) => {
+                self.pos = pos;
+                Ok(pos)
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )),
+        }
+    }
+}
+
+impl<S: Storage, H: CDBHash> CDB<S, H> {
+    /// Find the first record with the named key, returning a reader
+    /// for its value instead of a copy of it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Read;
+    ///
+    /// let cdb = cdb::CDB::open("tests/test1.cdb").unwrap();
+    /// let mut reader = cdb.get_reader(b"two").unwrap().unwrap();
+    /// assert_eq!(reader.len(), 7);
+    /// let mut value = String::new();
+    /// reader.read_to_string(&mut value).unwrap();
+    /// assert_eq!(value, "Goodbye");
+    /// ```
+    pub fn get_reader(&self, key: &[u8]) -> Option<Result<ValueReader<'_, S, H>>> {
+        let mut lookup = Lookup::new(self.hasher().hash(key));
+        lookup
+            .next(self, key)
+            .map(|r| r.and_then(|(dpos, dlen)| ValueReader::new(self, dpos, dlen)))
+    }
+}
@@ -0,0 +1,318 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::hash::{CDBHash, TRAILER_SIZE};
+use crate::reader::{CDB, Result};
+use crate::storage::Storage;
+
+/// The results of verifying the structure of a CDB.
+///
+/// See [`CDB::verify`](struct.CDB.html#method.verify)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Report {
+    /// The number of records found in the record region.
+    pub records: u64,
+    /// Every problem found, in the order they were found.
+    pub problems: Vec<Problem>,
+}
+
+impl Report {
+    /// Returns true if no problems were found.
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    fn problem(&mut self, offset: u64, kind: ProblemKind) {
+        self.problems.push(Problem { offset, kind });
+    }
+}
+
+/// A problem found while verifying a CDB.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Problem {
+    /// The byte offset in the file of the header entry, hash table
+    /// slot or record at fault.
+    pub offset: u64,
+    /// What is wrong at that offset.
+    pub kind: ProblemKind,
+}
+
+/// The kinds of [`Problem`](struct.Problem.html) found by verification.
+///
+/// Header entry problems are reported at the offset of the entry,
+/// slot problems at the offset of the slot, and record problems at
+/// the offset of the start of the record.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProblemKind {
+    /// A header entry points at a hash table that extends past the
+    /// end of the file.
+    TableOutOfBounds,
+    /// A hash table starts before the end of the records.
+    TableOverlapsRecords,
+    /// A hash table overlaps the hash table at the given offset.
+    TablesOverlap { other: u64 },
+    /// A record extends past the end of the record region.
+    RecordOutOfBounds,
+    /// The record region ends part way through a record header.
+    PartialRecord,
+    /// A slot points outside of the record region.
+    SlotOutOfBounds { pos: u64 },
+    /// A slot points inside the record region, but not at the start of
+    /// a record.
+    SlotMisaligned { pos: u64 },
+    /// The hash stored in a slot does not match the hash of the key of
+    /// the record it points at.
+    HashMismatch { stored: u64, actual: u32 },
+    /// A slot is in a different hash table than its hash selects.
+    WrongTable,
+    /// A slot cannot be reached by a lookup, as an empty slot comes
+    /// before it in the probe sequence for its hash.
+    SlotUnreachable,
+    /// A slot points at the same record as an earlier slot.
+    DuplicateSlot { pos: u64 },
+    /// No slot points at a record, so lookups cannot find it.
+    RecordUnreachable,
+    /// There are unexpected bytes after the last hash table.
+    TrailingBytes,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "offset {}: ", self.offset)?;
+        match self.kind {
+            ProblemKind::TableOutOfBounds => write!(f, "hash table extends past the end of file"),
+            ProblemKind::TableOverlapsRecords => write!(f, "hash table overlaps the records"),
+            ProblemKind::TablesOverlap { other } => {
+                write!(f, "hash table overlaps the hash table at {}", other)
+            }
+            ProblemKind::RecordOutOfBounds => write!(f, "record extends past the record region"),
+            ProblemKind::PartialRecord => write!(f, "partial record at end of record region"),
+            ProblemKind::SlotOutOfBounds { pos } => {
+                write!(f, "slot points outside the records at {}", pos)
+            }
+            ProblemKind::SlotMisaligned { pos } => {
+                write!(f, "slot points inside a record at {}", pos)
+            }
+            ProblemKind::HashMismatch { stored, actual } => write!(
+                f,
+                "slot hash {:#x} does not match key hash {:#x}",
+                stored, actual
+            ),
+            ProblemKind::WrongTable => write!(f, "slot is in the wrong hash table"),
+            ProblemKind::SlotUnreachable => write!(f, "slot is unreachable by lookups"),
+            ProblemKind::DuplicateSlot { pos } => {
+                write!(f, "slot duplicates the slot at {}", pos)
+            }
+            ProblemKind::RecordUnreachable => write!(f, "record is not in any hash table"),
+            ProblemKind::TrailingBytes => write!(f, "unexpected bytes after the hash tables"),
+        }
+    }
+}
+
+/// A record found while walking the record region.
+struct Record {
+    pos: u64,
+    hash: u32,
+    slot: Option<u64>,
+}
+
+/// A hash table named by the header.
+struct Table {
+    entry: u64,
+    pos: u64,
+    end: u64,
+}
+
+impl<S: Storage, H: CDBHash> CDB<S, H> {
+    /// Verify the structure of the whole database.
+    ///
+    /// This walks every record and every hash table, checking that each
+    /// record can be found by a lookup for its key and that nothing
+    /// in the file overlaps or points outside of where it should. It
+    /// reads the entire file, so it takes time proportional to its
+    /// size. Structural problems are collected into the returned
+    /// [`Report`](struct.Report.html); an error is only returned when
+    /// the underlying storage fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "std")] {
+    /// let cdb = cdb::CDB::open("tests/test1.cdb").unwrap();
+    /// let report = cdb.verify().unwrap();
+    /// for problem in &report.problems {
+    ///     println!("{}", problem);
+    /// }
+    /// assert!(report.is_ok());
+    /// # }
+    /// ```
+    pub fn verify(&self) -> Result<Report> {
+        let mut report = Report::default();
+        let format = self.format();
+        let pair_size = format.pair_size();
+        let header_size = format.header_size();
+        let size = self.storage().size();
+
+        let mut header = vec![0; header_size as usize];
+        self.storage().read_at(&mut header, 0)?;
+        let (data_end, _) = format.unpack2(&header);
+        let data_end = data_end.clamp(header_size, size);
+
+        let mut tables = Vec::with_capacity(256);
+        for (i, entry) in header.chunks(pair_size as usize).enumerate() {
+            let entry_pos = i as u64 * pair_size;
+            let (pos, slots) = format.unpack2(entry);
+            // Empty tables are never read, so their position does not
+            // matter.
+            if slots == 0 {
+                continue;
+            }
+            let end = slots
+                .checked_mul(pair_size)
+                .and_then(|len| len.checked_add(pos));
+            match end {
+                Some(end) if end <= size => {
+                    if pos < data_end {
+                        report.problem(entry_pos, ProblemKind::TableOverlapsRecords);
+                    }
+                    tables.push(Table {
+                        entry: entry_pos,
+                        pos,
+                        end,
+                    });
+                }
+                _ => report.problem(entry_pos, ProblemKind::TableOutOfBounds),
+            }
+        }
+
+        let mut records = self.verify_records(data_end, &mut report)?;
+
+        for (i, table) in tables.iter().enumerate() {
+            for other in &tables[..i] {
+                if table.pos < other.end && other.pos < table.end {
+                    report.problem(table.entry, ProblemKind::TablesOverlap { other: other.pos });
+                }
+            }
+            self.verify_table(table, data_end, &mut records, &mut report)?;
+        }
+
+        for record in &records {
+            if record.slot.is_none() {
+                report.problem(record.pos, ProblemKind::RecordUnreachable);
+            }
+        }
+
+        let tables_end = tables.iter().map(|t| t.end).max().unwrap_or(header_size);
+        let trailer_size = match self.hasher().id() {
+            Some(_) => TRAILER_SIZE as u64,
+            None => 0,
+        };
+        let end = tables_end.max(data_end) + trailer_size;
+        if size > end {
+            report.problem(end, ProblemKind::TrailingBytes);
+        }
+        Ok(report)
+    }
+
+    /// Walk all the records up to the end of the record region.
+    fn verify_records(&self, data_end: u64, report: &mut Report) -> Result<Vec<Record>> {
+        let format = self.format();
+        let pair_size = format.pair_size();
+        let mut buf = [0_u8; 16];
+        let buf = &mut buf[..pair_size as usize];
+        let mut records = Vec::new();
+        let mut pos = format.header_size();
+        while pos < data_end {
+            if data_end - pos < pair_size {
+                report.problem(pos, ProblemKind::PartialRecord);
+                break;
+            }
+            self.storage().read_at(buf, pos)?;
+            let (klen, dlen) = format.unpack2(buf);
+            let kpos = pos + pair_size;
+            let end = match kpos.checked_add(klen).and_then(|end| end.checked_add(dlen)) {
+                Some(end) if end <= data_end => end,
+                _ => {
+                    report.problem(pos, ProblemKind::RecordOutOfBounds);
+                    break;
+                }
+            };
+            let mut key = vec![0; klen as usize];
+            self.storage().read_at(&mut key, kpos)?;
+            records.push(Record {
+                pos,
+                hash: self.hasher().hash(&key),
+                slot: None,
+            });
+            report.records += 1;
+            pos = end;
+        }
+        Ok(records)
+    }
+
+    /// Check every slot in one hash table.
+    fn verify_table(
+        &self,
+        table: &Table,
+        data_end: u64,
+        records: &mut [Record],
+        report: &mut Report,
+    ) -> Result<()> {
+        let format = self.format();
+        let pair_size = format.pair_size();
+        let index = table.entry / pair_size;
+        let mut buf = vec![0; (table.end - table.pos) as usize];
+        self.storage().read_at(&mut buf, table.pos)?;
+        let slots: Vec<(u64, u64)> = buf
+            .chunks(pair_size as usize)
+            .map(|slot| format.unpack2(slot))
+            .collect();
+
+        for (i, &(hash, pos)) in slots.iter().enumerate() {
+            if pos == 0 {
+                continue;
+            }
+            let offset = table.pos + i as u64 * pair_size;
+            if pos < format.header_size() || pos >= data_end {
+                report.problem(offset, ProblemKind::SlotOutOfBounds { pos });
+                continue;
+            }
+            let record = match records.binary_search_by_key(&pos, |r| r.pos) {
+                Ok(n) => &mut records[n],
+                Err(_) => {
+                    report.problem(offset, ProblemKind::SlotMisaligned { pos });
+                    continue;
+                }
+            };
+            match record.slot {
+                Some(slot) => report.problem(offset, ProblemKind::DuplicateSlot { pos: slot }),
+                None => record.slot = Some(offset),
+            }
+            if hash != record.hash as u64 {
+                let actual = record.hash;
+                report.problem(
+                    offset,
+                    ProblemKind::HashMismatch {
+                        stored: hash,
+                        actual,
+                    },
+                );
+                continue;
+            }
+            if hash & 0xff != index {
+                report.problem(offset, ProblemKind::WrongTable);
+                continue;
+            }
+            let mut probe = (hash >> 8) as usize % slots.len();
+            while probe != i {
+                if slots[probe].1 == 0 {
+                    report.problem(offset, ProblemKind::SlotUnreachable);
+                    break;
+                }
+                probe = (probe + 1) % slots.len();
+            }
+        }
+        Ok(())
+    }
+}
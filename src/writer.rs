@@ -6,29 +6,143 @@ use std::iter;
 use std::path;
 use std::string;
 
-use crate::hash::hash;
-use crate::uint32;
+use crate::error::Error;
+use crate::format::Format;
+use crate::hash::{CDBHash, DjbHash, make_trailer};
 
-pub use std::io::Result;
+pub use crate::error::Result;
 
 #[derive(Clone, Copy, Debug)]
-struct HashPos {
+pub(crate) struct HashPos {
     hash: u32,
-    pos: u32,
+    pos: u64,
 }
 
-impl HashPos {
-    fn pack(&self, buf: &mut [u8]) {
-        uint32::pack2(buf, self.hash, self.pos);
-    }
+fn err_toobig<T>() -> Result<T> {
+    Err(Error::SizeLimit)
 }
 
-fn err_toobig<T>() -> Result<T> {
-    Err(io::Error::new(io::ErrorKind::Other, "File too big"))
+/// The positions of the records written so far, from which the hash
+/// tables are built.
+pub(crate) struct Index<H> {
+    format: Format,
+    hasher: H,
+    entries: Vec<Vec<HashPos>>,
+    pos: u64,
+    buf: [u8; 16],
+}
+
+impl<H: CDBHash> Index<H> {
+    pub(crate) fn new(format: Format, hasher: H) -> Index<H> {
+        Index {
+            format,
+            hasher,
+            entries: iter::repeat_n(vec![], 256).collect::<Vec<_>>(),
+            pos: format.header_size(),
+            buf: [0; 16],
+        }
+    }
+
+    /// The size of the header at the start of the file.
+    pub(crate) fn header_size(&self) -> usize {
+        self.format.header_size() as usize
+    }
+
+    fn pos_plus(&mut self, len: u64) -> Result<()> {
+        match self.pos.checked_add(len) {
+            Some(pos) if pos <= self.format.max_pos() => {
+                self.pos = pos;
+                Ok(())
+            }
+            _ => err_toobig(),
+        }
+    }
+
+    /// Record the addition of a record, returning the header to be
+    /// written ahead of its key and data.
+    pub(crate) fn add(&mut self, key: &[u8], data: &[u8]) -> Result<&[u8]> {
+        let (keylen, datalen) = (key.len() as u64, data.len() as u64);
+        if keylen >= self.format.max_pos() || datalen >= self.format.max_pos() {
+            return Err(Error::TooBig);
+        }
+        let hash = self.hasher.hash(key);
+        self.entries[(hash & 0xff) as usize].push(HashPos {
+            hash,
+            pos: self.pos,
+        });
+        let pair_size = self.format.pair_size();
+        self.pos_plus(pair_size)?;
+        self.pos_plus(keylen)?;
+        self.pos_plus(datalen)?;
+        let buf = &mut self.buf[..pair_size as usize];
+        self.format.pack2(buf, keylen, datalen);
+        Ok(buf)
+    }
+
+    /// Check that the hash tables will fit in the file, and return a
+    /// scratch table large enough to build any one of them.
+    pub(crate) fn start_tables(&self) -> Result<Vec<HashPos>> {
+        let maxsize = self.entries.iter().fold(1, |acc, e| max(acc, e.len() * 2));
+        let count = self.entries.iter().fold(0, |acc, e| acc + e.len());
+        if (maxsize + count) as u64 > self.format.max_pos() / self.format.pair_size() {
+            return err_toobig();
+        }
+        Ok(vec![HashPos { hash: 0, pos: 0 }; maxsize])
+    }
+
+    /// Pack hash table `i` into `buf`, recording its position in the
+    /// header.
+    pub(crate) fn build_table(
+        &mut self,
+        i: usize,
+        table: &mut [HashPos],
+        header: &mut [u8],
+        buf: &mut Vec<u8>,
+    ) -> Result<()> {
+        let pair_size = self.format.pair_size();
+        let len = self.entries[i].len() * 2;
+        let j = i * pair_size as usize;
+        self.format.pack2(&mut header[j..], self.pos, len as u64);
+
+        for e in self.entries[i].iter() {
+            let mut wh = (e.hash as usize >> 8) % len;
+            while table[wh].pos != 0 {
+                wh += 1;
+                if wh == len {
+                    wh = 0;
+                }
+            }
+            table[wh] = *e;
+        }
+
+        buf.clear();
+        buf.resize(len * pair_size as usize, 0);
+        for (hp, out) in table
+            .iter_mut()
+            .take(len)
+            .zip(buf.chunks_mut(pair_size as usize))
+        {
+            self.format.pack2(out, hp.hash as u64, hp.pos);
+            self.pos_plus(pair_size)?;
+            *hp = HashPos { hash: 0, pos: 0 };
+        }
+        Ok(())
+    }
+
+    /// The trailer to be written after the hash tables, recording the
+    /// hash function used.
+    pub(crate) fn trailer(&self) -> Option<[u8; 16]> {
+        self.hasher.id().map(make_trailer)
+    }
 }
 
 /// Base interface for making a CDB file.
 ///
+/// The CDB may be written to any writer which can seek, which defaults
+/// to a file. It is built in a single pass, except for the header,
+/// which is written over the start of the writer when the CDB is
+/// finished.
+///
 /// # Example
 ///
 /// ```no_run
@@ -41,111 +155,177 @@ fn err_toobig<T>() -> Result<T> {
 ///     Ok(())
 /// }
 /// ```
-pub struct CDBMake {
-    entries: Vec<Vec<HashPos>>,
-    pos: u32,
-    file: io::BufWriter<fs::File>,
+///
+/// Writing to memory:
+///
+/// ```
+/// let mut cdb = cdb::CDBMake::new(std::io::Cursor::new(Vec::new())).unwrap();
+/// cdb.add(b"one", b"Hello").unwrap();
+/// let data = cdb.finish().unwrap().into_inner();
+/// let cdb = cdb::CDB::new(data).unwrap();
+/// assert_eq!(cdb.get(b"one").unwrap().unwrap(), b"Hello");
+/// ```
+pub struct CDBMake<H = DjbHash, W: Write = fs::File> {
+    index: Index<H>,
+    file: io::BufWriter<W>,
 }
 
-impl CDBMake {
+impl<W: Write + Seek> CDBMake<DjbHash, W> {
     /// Create a new CDB maker.
-    pub fn new(file: fs::File) -> Result<CDBMake> {
-        let mut w = io::BufWriter::new(file);
-        let buf = [0; 2048];
-        w.seek(io::SeekFrom::Start(0))?;
-        w.write(&buf)?;
-        Ok(CDBMake {
-            entries: iter::repeat(vec![]).take(256).collect::<Vec<_>>(),
-            pos: 2048,
-            file: w,
-        })
+    pub fn new(file: W) -> Result<Self> {
+        CDBMake::with_format(file, Format::Cdb32)
     }
 
-    fn pos_plus(&mut self, len: u32) -> Result<()> {
-        if self.pos + len < len {
-            err_toobig()
-        } else {
-            self.pos += len;
-            Ok(())
-        }
+    /// Create a new CDB maker, writing a CDB in the given format.
+    pub fn with_format(file: W, format: Format) -> Result<Self> {
+        CDBMake::with_hasher(file, format, DjbHash)
     }
+}
 
-    fn add_end(&mut self, keylen: u32, datalen: u32, hash: u32) -> Result<()> {
-        self.entries[(hash & 0xff) as usize].push(HashPos {
-            hash: hash,
-            pos: self.pos,
-        });
-        self.pos_plus(8)?;
-        self.pos_plus(keylen)?;
-        self.pos_plus(datalen)?;
-        Ok(())
+impl<H: CDBHash, W: Write + Seek> CDBMake<H, W> {
+    /// Create a new CDB maker, writing a CDB in the given format with
+    /// keys hashed by the given hash function.
+    pub fn with_hasher(file: W, format: Format, hasher: H) -> Result<Self> {
+        let mut w = io::BufWriter::new(file);
+        let index = Index::new(format, hasher);
+        let buf = vec![0; index.header_size()];
+        w.seek(io::SeekFrom::Start(0))?;
+        w.write_all(&buf)?;
+        Ok(CDBMake { index, file: w })
     }
 
-    fn add_begin(&mut self, keylen: u32, datalen: u32) -> Result<()> {
-        let mut buf = [0; 8];
-        uint32::pack2(&mut buf[0..8], keylen, datalen);
-        self.file.write(&buf)?;
+    /// Add a record to the CDB file.
+    pub fn add(&mut self, key: &[u8], data: &[u8]) -> Result<()> {
+        let buf = self.index.add(key, data)?;
+        self.file.write_all(buf)?;
+        self.file.write_all(key)?;
+        self.file.write_all(data)?;
         Ok(())
     }
 
-    /// Add a record to the CDB file.
-    pub fn add(&mut self, key: &[u8], data: &[u8]) -> Result<()> {
-        if key.len() >= 0xffffffff || data.len() >= 0xffffffff {
-            return Err(io::Error::new(io::ErrorKind::Other, "Key or data too big"));
+    /// Finish writing to the CDB file and flush its contents,
+    /// returning the underlying writer positioned at the end of the
+    /// CDB.
+    pub fn finish(mut self) -> Result<W> {
+        let mut table = self.index.start_tables()?;
+        let mut header = vec![0_u8; self.index.header_size()];
+        let mut buf = Vec::new();
+        for i in 0..256 {
+            self.index
+                .build_table(i, &mut table, &mut header, &mut buf)?;
+            self.file.write_all(&buf)?;
         }
-        self.add_begin(key.len() as u32, data.len() as u32)?;
-        self.file.write(key)?;
-        self.file.write(data)?;
-        self.add_end(key.len() as u32, data.len() as u32, hash(&key[..]))
+        if let Some(trailer) = self.index.trailer() {
+            self.file.write_all(&trailer)?;
+        }
+
+        self.file.flush()?;
+        self.file.seek(io::SeekFrom::Start(0))?;
+        self.file.write_all(&header)?;
+        self.file.seek(io::SeekFrom::End(0))?;
+        Ok(self
+            .file
+            .into_inner()
+            .map_err(io::IntoInnerError::into_error)?)
     }
+}
 
+impl<H> CDBMake<H, fs::File> {
     /// Set the permissions on the underlying file.
     pub fn set_permissions(&self, perm: fs::Permissions) -> Result<()> {
-        self.file.get_ref().set_permissions(perm)
+        Ok(self.file.get_ref().set_permissions(perm)?)
     }
+}
 
-    /// Finish writing to the CDB file and flush its contents.
-    pub fn finish(mut self) -> Result<()> {
-        let mut buf = [0; 8];
+/// A CDB built in memory.
+///
+/// Records can be added one at a time with [`add`](#method.add), or
+/// all at once with `Extend` or `FromIterator`. The finished CDB is
+/// returned as bytes by [`finish`](#method.finish), which can be read
+/// with [`CDB::new`](struct.CDB.html#method.new).
+///
+/// # Example
+///
+/// ```
+/// let data = [("one", "Hello"), ("two", "Goodbye")]
+///     .into_iter()
+///     .collect::<cdb::CDBBuffer>()
+///     .finish()
+///     .unwrap();
+/// let cdb = cdb::CDB::new(data).unwrap();
+/// assert_eq!(cdb.get(b"two").unwrap().unwrap(), b"Goodbye");
+/// ```
+pub struct CDBBuffer<H = DjbHash> {
+    cdb: CDBMake<H, io::Cursor<Vec<u8>>>,
+    /// The first error from `extend`, which cannot report it.
+    error: Option<Error>,
+}
 
-        let maxsize = self.entries.iter().fold(1, |acc, e| max(acc, e.len() * 2));
-        let count = self.entries.iter().fold(0, |acc, e| acc + e.len());
-        if maxsize + count > (0xffffffff / 8) {
-            return err_toobig();
-        }
+impl CDBBuffer {
+    /// Create an empty CDB in memory.
+    pub fn new() -> CDBBuffer {
+        CDBBuffer::with_format(Format::Cdb32)
+    }
 
-        let mut table = vec![HashPos { hash: 0, pos: 0 }; maxsize];
+    /// Create an empty CDB in memory, in the given format.
+    pub fn with_format(format: Format) -> CDBBuffer {
+        CDBBuffer::with_hasher(format, DjbHash)
+    }
+}
 
-        let mut header = [0 as u8; 2048];
-        for i in 0..256 {
-            let len = self.entries[i].len() * 2;
-            let j = i * 8;
-            uint32::pack2(&mut header[j..j + 8], self.pos, len as u32);
-
-            for e in self.entries[i].iter() {
-                let mut wh = (e.hash as usize >> 8) % len;
-                while table[wh].pos != 0 {
-                    wh += 1;
-                    if wh == len {
-                        wh = 0;
-                    }
-                }
-                table[wh] = *e;
-            }
+impl Default for CDBBuffer {
+    fn default() -> Self {
+        CDBBuffer::new()
+    }
+}
+
+impl<H: CDBHash> CDBBuffer<H> {
+    /// Create an empty CDB in memory, in the given format with keys
+    /// hashed by the given hash function.
+    pub fn with_hasher(format: Format, hasher: H) -> CDBBuffer<H> {
+        // Writing the header to memory cannot fail.
+        let cdb = CDBMake::with_hasher(io::Cursor::new(Vec::new()), format, hasher).unwrap();
+        CDBBuffer { cdb, error: None }
+    }
+
+    /// Add a record to the CDB.
+    pub fn add(&mut self, key: &[u8], data: &[u8]) -> Result<()> {
+        self.cdb.add(key, data)
+    }
+
+    /// Finish the CDB and return its contents.
+    ///
+    /// If adding any of the records with `extend` or `collect` failed,
+    /// the first error is returned here instead.
+    pub fn finish(self) -> Result<Vec<u8>> {
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+        Ok(self.cdb.finish()?.into_inner())
+    }
+}
 
-            for hp in table.iter_mut().take(len) {
-                hp.pack(&mut buf);
-                self.file.write(&buf)?;
-                self.pos_plus(8)?;
-                *hp = HashPos { hash: 0, pos: 0 };
+impl<H: CDBHash, K: AsRef<[u8]>, V: AsRef<[u8]>> Extend<(K, V)> for CDBBuffer<H> {
+    /// Add the records, stopping at the first error, which is returned
+    /// by [`finish`](#method.finish).
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        if self.error.is_some() {
+            return;
+        }
+        for (key, value) in iter {
+            if let Err(err) = self.add(key.as_ref(), value.as_ref()) {
+                self.error = Some(err);
+                return;
             }
         }
+    }
+}
 
-        self.file.flush()?;
-        self.file.seek(io::SeekFrom::Start(0))?;
-        self.file.write(&header)?;
-        self.file.flush()?;
-        Ok(())
+impl<K: AsRef<[u8]>, V: AsRef<[u8]>> FromIterator<(K, V)> for CDBBuffer {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut cdb = CDBBuffer::new();
+        cdb.extend(iter);
+        cdb
     }
 }
 
@@ -169,10 +349,10 @@ impl CDBMake {
 ///     Ok(())
 /// }
 /// ```
-pub struct CDBWriter {
+pub struct CDBWriter<H = DjbHash> {
     dstname: String,
     tmpname: String,
-    cdb: Option<CDBMake>,
+    cdb: Option<CDBMake<H, fs::File>>,
 }
 
 impl CDBWriter {
@@ -183,6 +363,16 @@ impl CDBWriter {
         CDBWriter::with_suffix(filename, ".tmp")
     }
 
+    /// Safely create a new CDB file in the given format.
+    ///
+    /// The suffix for the temporary file defaults to `".tmp"`.
+    pub fn with_format<P: AsRef<path::Path> + string::ToString>(
+        filename: P,
+        format: Format,
+    ) -> Result<CDBWriter> {
+        CDBWriter::with_hasher(filename, format, DjbHash)
+    }
+
     /// Safely create a new CDB file, using a specific suffix for the temporary file.
     pub fn with_suffix<P: AsRef<path::Path> + string::ToString>(
         filename: P,
@@ -204,8 +394,42 @@ impl CDBWriter {
         filename: P,
         tmpname: Q,
     ) -> Result<CDBWriter> {
+        CDBWriter::with_filenames_and_hasher(filename, tmpname, Format::Cdb32, DjbHash)
+    }
+}
+
+impl<H: CDBHash> CDBWriter<H> {
+    /// Safely create a new CDB file in the given format, with keys
+    /// hashed by the given hash function.
+    ///
+    /// The suffix for the temporary file defaults to `".tmp"`.
+    pub fn with_hasher<P: AsRef<path::Path> + string::ToString>(
+        filename: P,
+        format: Format,
+        hasher: H,
+    ) -> Result<CDBWriter<H>> {
+        let mut tmpname = filename.to_string();
+        tmpname.push_str(".tmp");
+        CDBWriter::with_filenames_and_hasher(filename, tmpname, format, hasher)
+    }
+
+    /// Safely create a new CDB file in the given format, with keys
+    /// hashed by the given hash function, using two specific file
+    /// names.
+    ///
+    /// Note that the temporary file name must be on the same filesystem
+    /// as the destination, or else the final rename will fail.
+    pub fn with_filenames_and_hasher<
+        P: AsRef<path::Path> + string::ToString,
+        Q: AsRef<path::Path> + string::ToString,
+    >(
+        filename: P,
+        tmpname: Q,
+        format: Format,
+        hasher: H,
+    ) -> Result<CDBWriter<H>> {
         let file = fs::File::create(&tmpname)?;
-        let cdb = CDBMake::new(file)?;
+        let cdb = CDBMake::with_hasher(file, format, hasher)?;
         Ok(CDBWriter {
             dstname: filename.to_string(),
             tmpname: tmpname.to_string(),
@@ -235,10 +459,10 @@ impl CDBWriter {
     }
 }
 
-impl Drop for CDBWriter {
+impl<H> Drop for CDBWriter<H> {
     #[allow(unused_must_use)]
     fn drop(&mut self) {
-        if let Some(_) = self.cdb {
+        if self.cdb.is_some() {
             fs::remove_file(&self.tmpname);
         }
     }
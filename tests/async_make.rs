@@ -0,0 +1,62 @@
+#![cfg(feature = "tokio")]
+extern crate cdb;
+use std::fs;
+
+const RECORDS: &[(&[u8], &[u8])] = &[
+    (b"one", b"Hello"),
+    (b"two", b"Goodbye"),
+    (b"one", b", World!"),
+    (b"this key will be split across two reads", b"Got it."),
+];
+
+#[tokio::test]
+async fn test_async_make() {
+    let sync_name = "tests/async-make-sync.cdb";
+    let async_name = "tests/async-make-async.cdb";
+
+    let mut cdb = cdb::CDBWriter::create(sync_name).unwrap();
+    for (key, value) in RECORDS {
+        cdb.add(key, value).unwrap();
+    }
+    cdb.finish().unwrap();
+
+    let mut cdb = cdb::AsyncCDBWriter::create(async_name).await.unwrap();
+    for (key, value) in RECORDS {
+        cdb.add(key, value).await.unwrap();
+    }
+    cdb.finish().await.unwrap();
+
+    assert!(fs::metadata(format!("{}.tmp", async_name)).is_err());
+    assert_eq!(fs::read(sync_name).unwrap(), fs::read(async_name).unwrap());
+
+    let cdb = cdb::CDB::open(async_name).unwrap();
+    assert_eq!(cdb.get(b"two").unwrap().unwrap(), b"Goodbye");
+
+    fs::remove_file(sync_name).unwrap();
+    fs::remove_file(async_name).unwrap();
+}
+
+#[tokio::test]
+async fn test_async_finish_position() {
+    use tokio::io::AsyncSeekExt;
+
+    let filename =
+        std::env::temp_dir().join(format!("cdb-async-finish-{}.cdb", std::process::id()));
+    let file = tokio::fs::File::create(&filename).await.unwrap();
+    let mut cdb = cdb::AsyncCDBMake::new(file).await.unwrap();
+    cdb.add(b"one", b"Hello").await.unwrap();
+    let mut file = cdb.finish().await.unwrap();
+    let len = file.metadata().await.unwrap().len();
+    assert_eq!(file.stream_position().await.unwrap(), len);
+    fs::remove_file(&filename).unwrap();
+}
+
+#[tokio::test]
+async fn test_async_drop() {
+    let filename = "tests/async-drop.cdb";
+    let mut cdb = cdb::AsyncCDBWriter::create(filename).await.unwrap();
+    cdb.add(b"one", b"Hello").await.unwrap();
+    drop(cdb);
+    assert!(fs::metadata(filename).is_err());
+    assert!(fs::metadata(format!("{}.tmp", filename)).is_err());
+}
@@ -0,0 +1,44 @@
+#![cfg(feature = "tokio")]
+extern crate cdb;
+
+use futures_core::Stream;
+use std::future::poll_fn;
+use std::pin::Pin;
+
+async fn collect<S: Stream + Unpin>(mut stream: S) -> Vec<S::Item> {
+    let mut items = Vec::new();
+    while let Some(item) = poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await {
+        items.push(item);
+    }
+    items
+}
+
+#[tokio::test]
+async fn test_get() {
+    let cdb = cdb::AsyncCDB::open("tests/test1.cdb").await.unwrap();
+    assert_eq!(cdb.get(b"two").await.unwrap().unwrap(), b"Goodbye");
+    assert!(cdb.get(b"three").await.is_none());
+}
+
+#[tokio::test]
+async fn test_find() {
+    let cdb = cdb::AsyncCDB::open("tests/test1.cdb").await.unwrap();
+    let values: Vec<_> = collect(cdb.find(b"one"))
+        .await
+        .into_iter()
+        .map(|r| r.unwrap())
+        .collect();
+    assert_eq!(values, vec![b"Hello".to_vec(), b", World!".to_vec()]);
+    assert!(collect(cdb.find(b"three")).await.is_empty());
+}
+
+#[tokio::test]
+async fn test_iter() {
+    let cdb = cdb::AsyncCDB::new(cdb::CDB::open("tests/test1.cdb").unwrap());
+    let records = collect(cdb.iter()).await;
+    let expected = cdb::CDB::open("tests/test1.cdb").unwrap();
+    assert_eq!(records.len(), 4);
+    for (record, expected) in records.into_iter().zip(expected.iter()) {
+        assert_eq!(record.unwrap(), expected.unwrap());
+    }
+}
@@ -0,0 +1,119 @@
+#![cfg(feature = "std")]
+extern crate cdb;
+use std::fs;
+
+/// The cases in tests/conformance, each a cdbmake input file and the
+/// database built from it by generate.py. The databases checked in
+/// were built by its port of cdb_make.c, not by the reference cdbmake,
+/// so they only show agreement with the port. See
+/// tests/conformance/SOURCE.
+const CASES: &[&str] = &[
+    "empty",
+    "empty-key",
+    "empty-value",
+    "empty-record",
+    "duplicates",
+    "full-table",
+    "collisions",
+    "binary",
+];
+
+type Records = Vec<(Vec<u8>, Vec<u8>)>;
+
+/// Parse records in the input format of cdbmake.
+fn parse(text: &[u8]) -> Records {
+    fn number(text: &[u8], end: u8) -> (usize, &[u8]) {
+        let i = text.iter().position(|&c| c == end).unwrap();
+        let n = std::str::from_utf8(&text[..i]).unwrap().parse().unwrap();
+        (n, &text[i + 1..])
+    }
+    let mut records = Vec::new();
+    let mut text = text;
+    while text[0] == b'+' {
+        let (klen, rest) = number(&text[1..], b',');
+        let (dlen, rest) = number(rest, b':');
+        assert_eq!(&rest[klen..klen + 2], b"->");
+        let key = rest[..klen].to_vec();
+        let data = rest[klen + 2..klen + 2 + dlen].to_vec();
+        assert_eq!(rest[klen + 2 + dlen], b'\n');
+        records.push((key, data));
+        text = &rest[klen + 3 + dlen..];
+    }
+    assert_eq!(text, b"\n");
+    records
+}
+
+fn load(name: &str) -> (Records, Vec<u8>) {
+    let text = fs::read(format!("tests/conformance/{}.txt", name)).unwrap();
+    let data = fs::read(format!("tests/conformance/{}.cdb", name)).unwrap();
+    (parse(&text), data)
+}
+
+/// Check that the reader finds exactly the given records.
+fn check_read(name: &str, records: &[(Vec<u8>, Vec<u8>)], data: &[u8]) {
+    let cdb = cdb::CDB::new(data).unwrap();
+    assert_eq!(cdb.len().unwrap(), records.len() as u64, "{}", name);
+    let all: Vec<_> = cdb.iter().map(Result::unwrap).collect();
+    assert_eq!(all, records, "{}", name);
+    for (key, _) in records {
+        let expected: Vec<_> = records
+            .iter()
+            .filter(|(k, _)| k == key)
+            .map(|(_, v)| v.clone())
+            .collect();
+        let found: Vec<_> = cdb.find(key).map(Result::unwrap).collect();
+        assert_eq!(found, expected, "{}: {:?}", name, key);
+    }
+    assert!(cdb.get(b"missing").is_none(), "{}", name);
+}
+
+#[test]
+fn test_make() {
+    for name in CASES {
+        let (records, expected) = load(name);
+        let data = records
+            .into_iter()
+            .collect::<cdb::CDBBuffer>()
+            .finish()
+            .unwrap();
+        assert!(data == expected, "{} differs from the corpus", name);
+    }
+}
+
+#[test]
+fn test_read() {
+    for name in CASES {
+        let (records, data) = load(name);
+        check_read(name, &records, &data);
+        let report = cdb::CDB::new(&data[..]).unwrap().verify().unwrap();
+        assert!(report.is_ok(), "{}: {:?}", name, report);
+    }
+}
+
+#[test]
+fn test_empty() {
+    let (_, data) = load("empty");
+    assert_eq!(data.len(), 2048);
+    let cdb = cdb::CDB::open("tests/conformance/empty.cdb").unwrap();
+    assert!(cdb.is_empty().unwrap());
+    assert!(cdb.iter().next().is_none());
+    assert_eq!(cdb::Format::detect(&data).unwrap(), cdb::Format::Cdb32);
+}
+
+#[test]
+fn test_trailing_data() {
+    // Trailing data after the hash tables is ignored by readers.
+    for name in CASES {
+        let (records, mut data) = load(name);
+        data.extend_from_slice(b"trailing data\n");
+        check_read(name, &records, &data);
+        let report = cdb::CDB::new(&data[..]).unwrap().verify().unwrap();
+        assert!(matches!(
+            report.problems[..],
+            [cdb::Problem {
+                kind: cdb::ProblemKind::TrailingBytes,
+                ..
+            }]
+        ));
+    }
+}
@@ -0,0 +1,158 @@
+#!/usr/bin/env python3
+"""Generate the conformance corpus.
+
+Each case is a cdbmake input file, NAME.txt, and the database built from
+it, NAME.cdb. The databases are meant to be built by the reference
+cdbmake from cdb-0.75, which must be on the PATH:
+
+    python3 tests/conformance/generate.py
+
+This runs `cdbmake NAME.cdb NAME.cdb.tmp < NAME.txt` for each case. When
+tinycdb's `cdb` is also on the PATH, each case is built again with
+`cdb -c -t NAME.tiny.tmp NAME.tiny NAME.txt` as a cross-check, and any
+difference is an error.
+
+Without the reference tools, `--port` builds the databases with the port
+of cdb_make.c below instead. It is first checked to reproduce
+tests/test1.cdb and tests/test2.cdb, both made by the reference cdbmake,
+byte for byte. The command that built the databases checked in is
+recorded in SOURCE.
+"""
+
+import os
+import shutil
+import struct
+import subprocess
+import sys
+
+HERE = os.path.dirname(os.path.abspath(__file__))
+TESTS = os.path.dirname(HERE)
+
+
+def djb_hash(key):
+    h = 5381
+    for c in key:
+        h = ((h << 5) + h) & 0xFFFFFFFF
+        h ^= c
+    return h
+
+
+def cdb_make(records):
+    """A port of cdb_make.c from cdb-0.75."""
+    body = bytearray()
+    pos = 2048
+    buckets = [[] for _ in range(256)]
+    for key, data in records:
+        h = djb_hash(key)
+        buckets[h & 0xFF].append((h, pos))
+        body += struct.pack("<LL", len(key), len(data)) + key + data
+        pos += 8 + len(key) + len(data)
+    header = bytearray()
+    tables = bytearray()
+    for bucket in buckets:
+        length = len(bucket) * 2
+        header += struct.pack("<LL", pos, length)
+        table = [(0, 0)] * length
+        for h, p in bucket:
+            where = (h >> 8) % length
+            while table[where][1]:
+                where = (where + 1) % length
+            table[where] = (h, p)
+        for h, p in table:
+            tables += struct.pack("<LL", h, p)
+        pos += 8 * length
+    return bytes(header + body + tables)
+
+
+def format_input(records):
+    out = bytearray()
+    for key, data in records:
+        out += b"+%d,%d:%s->%s\n" % (len(key), len(data), key, data)
+    return bytes(out + b"\n")
+
+
+def parse_input(text):
+    records = []
+    i = 0
+    while text[i : i + 1] == b"+":
+        comma = text.index(b",", i)
+        colon = text.index(b":", comma)
+        klen = int(text[i + 1 : comma])
+        dlen = int(text[comma + 1 : colon])
+        key = text[colon + 1 : colon + 1 + klen]
+        data = text[colon + 3 + klen : colon + 3 + klen + dlen]
+        records.append((key, data))
+        i = colon + 3 + klen + dlen + 1
+    return records
+
+
+def colliding_keys(count, same_slot):
+    """Find keys which all hash into table 0. With same_slot, they also
+    all start probing from the last slot of the table, so that probing
+    wraps around to the start."""
+    length = count * 2
+    keys = []
+    n = 0
+    while len(keys) < count:
+        key = b"k%d" % n
+        h = djb_hash(key)
+        if h & 0xFF == 0 and (not same_slot or (h >> 8) % length == length - 1):
+            keys.append(key)
+        n += 1
+    return keys
+
+
+CASES = {
+    "empty": [],
+    "empty-key": [(b"", b"no key"), (b"one", b"Hello"), (b"", b"again")],
+    "empty-value": [(b"one", b""), (b"two", b"Goodbye"), (b"one", b"")],
+    "empty-record": [(b"one", b"Hello"), (b"", b"")],
+    "duplicates": [(b"dup", b"%d" % i) for i in range(500)] + [(b"other", b"x")],
+    "full-table": [(key, key.upper()) for key in colliding_keys(100, False)],
+    "collisions": [(key, key.upper()) for key in colliding_keys(8, True)],
+    "binary": [(bytes(range(256)), bytes(range(255, -1, -1))), (b"\n+:->", b",\n")],
+}
+
+
+def build(name, records, port):
+    txt = os.path.join(HERE, name + ".txt")
+    out = os.path.join(HERE, name + ".cdb")
+    with open(txt, "wb") as f:
+        f.write(format_input(records))
+    if port:
+        with open(out, "wb") as f:
+            f.write(cdb_make(records))
+        return
+    with open(txt, "rb") as f:
+        subprocess.run(["cdbmake", out, out + ".tmp"], stdin=f, check=True)
+    if shutil.which("cdb"):
+        tiny = os.path.join(HERE, name + ".tiny")
+        subprocess.run(["cdb", "-c", "-t", tiny + ".tmp", tiny, txt], check=True)
+        with open(out, "rb") as a, open(tiny, "rb") as b:
+            same = a.read() == b.read()
+        os.remove(tiny)
+        if not same:
+            sys.exit("cdbmake and tinycdb disagree on " + name)
+
+
+def check_port():
+    with open(os.path.join(TESTS, "test1.txt"), "rb") as f:
+        test1 = parse_input(f.read())
+    script = subprocess.run(
+        ["sh", os.path.join(TESTS, "test2.sh")], capture_output=True, check=True
+    )
+    test2 = parse_input(script.stdout)
+    for records, name in [(test1, "test1.cdb"), (test2, "test2.cdb")]:
+        with open(os.path.join(TESTS, name), "rb") as f:
+            if cdb_make(records) != f.read():
+                sys.exit("the cdb_make port does not reproduce " + name)
+
+
+if __name__ == "__main__":
+    port = sys.argv[1:] == ["--port"]
+    if port:
+        check_port()
+    elif not shutil.which("cdbmake"):
+        sys.exit("cdbmake from cdb-0.75 is not on the PATH; see --port")
+    for name, records in CASES.items():
+        build(name, records, port)
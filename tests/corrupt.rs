@@ -0,0 +1,140 @@
+#![cfg(feature = "std")]
+extern crate cdb;
+use cdb::{CDB, CDBHash, Cursor, Format};
+use std::fs;
+use std::io::Read;
+
+/// A small deterministic generator, so that failures can be reproduced.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+/// Run every read operation over the file. None of them may panic, and
+/// every iterator must finish.
+fn exercise(data: &[u8], format: Format) {
+    let limit = data.len() + 1;
+    let cdb = match CDB::with_format(data, format) {
+        Ok(cdb) => cdb,
+        Err(_) => return,
+    };
+    let _ = cdb.len();
+    let _ = cdb.stats();
+    let _ = cdb.verify();
+
+    let mut iter = cdb.iter();
+    assert!(iter.by_ref().take(limit).count() < limit);
+    let _ = cdb.iter_from(iter.cursor());
+    assert!(cdb.iter_ref().take(limit).count() < limit);
+
+    let mut keys = vec![b"one".to_vec(), b"two".to_vec(), Vec::new()];
+    for offset in cdb.iter_offsets().take(limit).flatten() {
+        if let Ok((key, _)) = cdb.record_at(offset) {
+            keys.push(key);
+        }
+        let _ = cdb.record_ref_at(offset);
+        let _ = cdb.iter_ref_from(Cursor::new(offset));
+    }
+    for key in &keys {
+        assert!(cdb.find(key).take(limit).count() < limit);
+        assert!(cdb.find_ref(key).take(limit).count() < limit);
+        assert!(cdb.find_offsets(key).take(limit).count() < limit);
+        let _ = cdb.count(key);
+        if let Some(Ok(mut reader)) = cdb.get_reader(key) {
+            let _ = reader.read_to_end(&mut Vec::new());
+        }
+    }
+    let _ = cdb.get_many(&keys);
+}
+
+fn mutate(original: &[u8], format: Format, seed: u64) {
+    let mut rng = Rng(seed);
+    for _ in 0..200 {
+        let mut data = original.to_vec();
+        for _ in 0..1 + rng.below(4) {
+            let pos = rng.below(data.len());
+            match rng.below(3) {
+                0 => data[pos] = rng.next() as u8,
+                1 => data[pos] = 0xff,
+                _ => data.truncate(pos),
+            }
+            if data.is_empty() {
+                break;
+            }
+        }
+        exercise(&data, format);
+    }
+}
+
+#[test]
+fn test_mutated() {
+    let data = fs::read("tests/test1.cdb").unwrap();
+    for seed in 1..50 {
+        mutate(&data, Format::Cdb32, seed);
+    }
+
+    let mut cdb = cdb::CDBBuffer::with_format(Format::Cdb64);
+    cdb.add(b"one", b"Hello").unwrap();
+    cdb.add(b"two", b"Goodbye").unwrap();
+    cdb.add(b"one", b", World!").unwrap();
+    let data = cdb.finish().unwrap();
+    for seed in 1..50 {
+        mutate(&data, Format::Cdb64, seed);
+    }
+}
+
+#[test]
+fn test_overflow() {
+    let data = fs::read("tests/test1.cdb").unwrap();
+    let hash = cdb::DjbHash.hash(b"one");
+    let table = (hash & 0xff) as usize * 8;
+
+    // A hash table whose end is past the end of the address space.
+    let mut bad = data.clone();
+    bad[table..table + 8].copy_from_slice(&[0xf0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x0f]);
+    let cdb = CDB::new(&bad[..]).unwrap();
+    let mut find = cdb.find(b"one");
+    assert!(find.next().unwrap().unwrap_err().is_corrupt());
+    assert!(find.next().is_none());
+    exercise(&bad, Format::Cdb32);
+
+    // Lengths which sum past the end of the address space.
+    let mut bad = data.clone();
+    bad[0..4].copy_from_slice(&[0xff; 4]);
+    bad[2048..2056].copy_from_slice(&[0xff; 8]);
+    exercise(&bad, Format::Cdb32);
+    let mut wide = vec![0_u8; 4096];
+    wide[..8].copy_from_slice(&[0xff; 8]);
+    let table = (hash & 0xff) as usize * 16;
+    wide[table..table + 8].copy_from_slice(&(u64::MAX - 8).to_le_bytes());
+    wide[table + 8..table + 16].copy_from_slice(&(u64::MAX / 2).to_le_bytes());
+    wide.extend_from_slice(&[0xff; 64]);
+    let cdb = CDB::with_format(&wide[..], Format::Cdb64).unwrap();
+    assert!(cdb.get(b"one").unwrap().unwrap_err().is_corrupt());
+    exercise(&wide, Format::Cdb64);
+}
+
+#[test]
+fn test_stops_after_error() {
+    let mut data = fs::read("tests/test1.cdb").unwrap();
+    data[2048 + 4..2048 + 8].copy_from_slice(&[0xff, 0xff, 0xff, 0x7f]);
+    let cdb = CDB::new(&data[..]).unwrap();
+
+    let mut iter = cdb.iter();
+    assert!(iter.next().unwrap().is_err());
+    assert!(iter.next().is_none());
+    // The cursor is left at the record which could not be read.
+    assert_eq!(iter.cursor().offset(), 2048);
+    assert_eq!(cdb.iter_ref().count(), 1);
+    assert_eq!(cdb.iter_offsets().count(), 1);
+}
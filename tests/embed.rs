@@ -0,0 +1,74 @@
+#![cfg(feature = "std")]
+extern crate cdb;
+use std::fs;
+
+static TEST1: cdb::CDB<&[u8]> = cdb::CDB::from_static(include_bytes!("test1.cdb"));
+
+#[test]
+fn test_compile() {
+    for name in ["empty", "empty-key", "duplicates", "binary"] {
+        let source = format!("tests/conformance/{}.txt", name);
+        let filename = format!("tests/embed-{}.cdb", name);
+        let records = cdb::compile_to(&source, &filename).unwrap();
+        let data = fs::read(&filename).unwrap();
+        fs::remove_file(&filename).unwrap();
+        let expected = fs::read(format!("tests/conformance/{}.cdb", name)).unwrap();
+        assert!(data == expected, "{}", name);
+        assert_eq!(records, cdb::CDB::new(&data[..]).unwrap().len().unwrap());
+    }
+}
+
+#[test]
+fn test_compile_errors() {
+    let source = "tests/embed-bad.txt";
+    let filename = "tests/embed-bad.cdb";
+    let cases: &[(&[u8], u64)] = &[
+        (b"", 0),
+        (b"+3,5:one->Hello\n", 16),
+        (b"+3,5:one->Hello\nx", 16),
+        (b"+3,5:one-Hello\n\n", 9),
+        (b"+3,5:one->Hello!\n\n", 15),
+        (b"+3,50:one->Hello\n\n", 18),
+        (b"+,5:one->Hello\n\n", 1),
+        (b"+3;5:one->Hello\n\n", 2),
+        (b"+99999999999999999999,5:one->Hello\n\n", 20),
+    ];
+    for &(input, offset) in cases {
+        fs::write(source, input).unwrap();
+        let err = cdb::compile_to(source, filename).unwrap_err();
+        assert!(
+            matches!(err, cdb::Error::Syntax { offset: o } if o == offset),
+            "{:?}: {:?}",
+            String::from_utf8_lossy(input),
+            err
+        );
+        assert!(!err.is_corrupt());
+        assert!(fs::metadata(filename).is_err());
+    }
+    fs::remove_file(source).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_compile_non_utf8() {
+    use std::os::unix::ffi::OsStrExt;
+    let dest = std::path::Path::new(std::ffi::OsStr::from_bytes(b"tests/embed-\xff.cdb"));
+    let err = cdb::compile_to("tests/conformance/empty.txt", dest).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(fs::metadata(dest).is_err());
+}
+
+#[test]
+fn test_from_static() {
+    assert_eq!(TEST1.get_ref(b"two").unwrap().unwrap(), b"Goodbye");
+    let mut i = TEST1.find_ref(b"one");
+    assert_eq!(i.next().unwrap().unwrap(), b"Hello");
+    assert_eq!(i.next().unwrap().unwrap(), b", World!");
+    assert!(i.next().is_none());
+    assert_eq!(TEST1.iter_ref().count(), 4);
+
+    // Nothing is checked, but a bad slice only produces errors.
+    static SHORT: cdb::CDB<&[u8]> = cdb::CDB::from_static(b"short");
+    assert!(SHORT.get(b"one").unwrap().unwrap_err().is_corrupt());
+    assert!(SHORT.iter().next().unwrap().is_err());
+}
@@ -0,0 +1,81 @@
+#![cfg(feature = "std")]
+extern crate cdb;
+use cdb::Format;
+use std::fs;
+
+fn make(filename: &str, format: Format) {
+    let mut cdb = cdb::CDBWriter::with_format(filename, format).unwrap();
+    cdb.add(b"one", b"Hello").unwrap();
+    cdb.add(b"two", b"Goodbye").unwrap();
+    cdb.add(b"one", b", World!").unwrap();
+    cdb.add(b"this key will be split across two reads", b"Got it.")
+        .unwrap();
+    cdb.finish().unwrap();
+}
+
+#[test]
+fn test_cdb64() {
+    let filename = "tests/format-64.cdb";
+    make(filename, Format::Cdb64);
+
+    let data = fs::read(filename).unwrap();
+    // The first record follows the 4096 byte header, with 64-bit lengths.
+    assert_eq!(
+        &data[4096..4096 + 16],
+        &[3, 0, 0, 0, 0, 0, 0, 0, 5, 0, 0, 0, 0, 0, 0, 0]
+    );
+    assert_eq!(&data[4096 + 16..4096 + 24], b"oneHello");
+    assert_eq!(Format::detect(&data).unwrap(), Format::Cdb64);
+    assert!(cdb::CDB::new(&data[..]).unwrap().get(b"one").is_none());
+
+    let cdb = cdb::CDB::with_format(&data[..], Format::Cdb64).unwrap();
+    let mut i = cdb.find(b"one");
+    assert_eq!(i.next().unwrap().unwrap(), b"Hello");
+    assert_eq!(i.next().unwrap().unwrap(), b", World!");
+    assert!(i.next().is_none());
+    assert_eq!(
+        cdb.get_ref(b"this key will be split across two reads")
+            .unwrap()
+            .unwrap(),
+        b"Got it."
+    );
+    assert_eq!(cdb.iter().count(), 4);
+
+    let cdb = cdb::CDB::open_detect(filename).unwrap();
+    assert_eq!(cdb.format(), Format::Cdb64);
+    assert_eq!(cdb.get(b"two").unwrap().unwrap(), b"Goodbye");
+
+    fs::remove_file(filename).unwrap();
+}
+
+#[test]
+fn test_convert() {
+    let original = "tests/convert-32.cdb";
+    let converted = "tests/convert-64.cdb";
+    let roundtrip = "tests/convert-roundtrip.cdb";
+    make(original, Format::Cdb32);
+
+    cdb::convert(original, converted, Format::Cdb64).unwrap();
+    let cdb = cdb::CDB::open_detect(converted).unwrap();
+    assert_eq!(cdb.format(), Format::Cdb64);
+    let records: Vec<_> = cdb.iter().map(|r| r.unwrap()).collect();
+    let expected: Vec<_> = cdb::CDB::open(original)
+        .unwrap()
+        .iter()
+        .map(|r| r.unwrap())
+        .collect();
+    assert_eq!(records, expected);
+
+    cdb::convert(converted, roundtrip, Format::Cdb32).unwrap();
+    assert_eq!(fs::read(original).unwrap(), fs::read(roundtrip).unwrap());
+
+    fs::remove_file(original).unwrap();
+    fs::remove_file(converted).unwrap();
+    fs::remove_file(roundtrip).unwrap();
+}
+
+#[test]
+fn test_detect_invalid() {
+    assert!(Format::detect(&vec![0xff_u8; 8192]).is_err());
+    assert!(Format::detect(&vec![0_u8; 100]).is_err());
+}
@@ -0,0 +1,58 @@
+#![cfg(feature = "std")]
+extern crate cdb;
+use cdb::{CDB, CDBWriter, DjbHash, Format, Xxh32Hash};
+use std::fs;
+
+#[test]
+fn test_xxh32() {
+    let filename = "tests/hash-xxh32.cdb";
+    let hasher = Xxh32Hash::new(42);
+    let mut cdb = CDBWriter::with_hasher(filename, Format::Cdb32, hasher).unwrap();
+    for i in 0..100 {
+        cdb.add(format!("key{}", i).as_bytes(), format!("{}", i).as_bytes())
+            .unwrap();
+    }
+    cdb.add(b"key7", b"again").unwrap();
+    cdb.finish().unwrap();
+
+    let data = fs::read(filename).unwrap();
+    let cdb = CDB::with_hasher(&data[..], Format::Cdb32, hasher).unwrap();
+    assert_eq!(cdb.get(b"key42").unwrap().unwrap(), b"42");
+    let values: Vec<_> = cdb.find(b"key7").map(|r| r.unwrap()).collect();
+    assert_eq!(values, vec![b"7".to_vec(), b"again".to_vec()]);
+    assert!(cdb.get(b"key100").is_none());
+    assert_eq!(cdb.iter().count(), 101);
+
+    assert!(CDB::new(&data[..]).is_err());
+    assert!(CDB::with_hasher(&data[..], Format::Cdb32, Xxh32Hash::new(43)).is_err());
+    assert_eq!(Format::detect(&data).unwrap(), Format::Cdb32);
+
+    fs::remove_file(filename).unwrap();
+}
+
+#[test]
+fn test_xxh32_cdb64() {
+    let filename = "tests/hash-xxh32-64.cdb";
+    let tmpname = "tests/hash-xxh32-64.new";
+    let hasher = Xxh32Hash::new(7);
+    let mut cdb =
+        CDBWriter::with_filenames_and_hasher(filename, tmpname, Format::Cdb64, hasher).unwrap();
+    cdb.add(b"one", b"Hello").unwrap();
+    assert!(fs::metadata(tmpname).is_ok());
+    cdb.finish().unwrap();
+    assert!(fs::metadata(tmpname).is_err());
+
+    let data = fs::read(filename).unwrap();
+    let cdb = CDB::with_hasher(&data[..], Format::Cdb64, hasher).unwrap();
+    assert_eq!(cdb.get_ref(b"one").unwrap().unwrap(), b"Hello");
+    assert!(CDB::with_format(&data[..], Format::Cdb64).is_err());
+
+    fs::remove_file(filename).unwrap();
+}
+
+#[test]
+fn test_djb_mismatch() {
+    let data = fs::read("tests/test1.cdb").unwrap();
+    assert!(CDB::with_hasher(&data[..], Format::Cdb32, DjbHash).is_ok());
+    assert!(CDB::with_hasher(&data[..], Format::Cdb32, Xxh32Hash::new(0)).is_err());
+}
@@ -1,5 +1,7 @@
+#![cfg(feature = "std")]
 extern crate cdb;
 use std::fs;
+use std::io;
 
 macro_rules! noerr {
     ( $e:expr ) => {
@@ -50,3 +52,48 @@ fn test_make() {
 
     noerr!(fs::remove_file(filename));
 }
+
+#[test]
+fn test_make_memory() {
+    let mut cdb = cdb::CDBMake::new(io::Cursor::new(Vec::new())).unwrap();
+    noerr!(cdb.add(b"one", b"Hello"));
+    noerr!(cdb.add(b"two", b"Goodbye"));
+    noerr!(cdb.add(b"one", b", World!"));
+    noerr!(cdb.add(b"this key will be split across two reads", b"Got it."));
+    let data = cdb.finish().unwrap().into_inner();
+    assert_eq!(data, fs::read("tests/test1.cdb").unwrap());
+}
+
+#[test]
+fn test_buffer() {
+    let records = [("one", "Hello"), ("two", "Goodbye"), ("one", ", World!")];
+    let data = records
+        .iter()
+        .copied()
+        .collect::<cdb::CDBBuffer>()
+        .finish()
+        .unwrap();
+    let mut cdb = cdb::CDBMake::new(io::Cursor::new(Vec::new())).unwrap();
+    for (key, value) in records {
+        noerr!(cdb.add(key.as_bytes(), value.as_bytes()));
+    }
+    assert_eq!(data, cdb.finish().unwrap().into_inner());
+
+    let mut buffer = cdb::CDBBuffer::with_format(cdb::Format::Cdb64);
+    buffer.extend(records.iter().map(|&(k, v)| (k.to_uppercase(), v)));
+    noerr!(buffer.add(b"", b""));
+    let data = buffer.finish().unwrap();
+    let cdb = cdb::CDB::with_format(&data[..], cdb::Format::Cdb64).unwrap();
+    assert_eq!(cdb.len().unwrap(), 4);
+    assert_eq!(cdb.get(b"TWO").unwrap().unwrap(), b"Goodbye");
+
+    assert_eq!(cdb::CDBBuffer::new().finish().unwrap().len(), 2048);
+}
+
+#[test]
+fn test_finish_position() {
+    let mut cdb = cdb::CDBMake::new(io::Cursor::new(Vec::new())).unwrap();
+    noerr!(cdb.add(b"one", b"Hello"));
+    let cursor = cdb.finish().unwrap();
+    assert_eq!(cursor.position(), cursor.get_ref().len() as u64);
+}
@@ -0,0 +1,49 @@
+#![cfg(all(unix, feature = "std"))]
+extern crate cdb;
+use cdb::{Advice, MapOptions, Prefault};
+
+#[test]
+fn test_map_options() {
+    for advice in [Advice::Normal, Advice::Random, Advice::Sequential] {
+        for prefault in [Prefault::HashTables, Prefault::All] {
+            let cdb = MapOptions::new()
+                .advice(advice)
+                .prefault(prefault)
+                .open("tests/test1.cdb")
+                .unwrap();
+            assert_eq!(cdb.get(b"two").unwrap().unwrap(), b"Goodbye");
+        }
+    }
+
+    let cdb = MapOptions::new()
+        .populate(true)
+        .open("tests/test1.cdb")
+        .unwrap();
+    let residency = cdb.residency().unwrap();
+    assert_eq!(residency.size, cdb.storage().as_ref().len() as u64);
+    assert_eq!(residency.resident, residency.size);
+
+    // Locking is subject to resource limits, so only check that it
+    // does not break the mapping when it succeeds.
+    if let Ok(cdb) = MapOptions::new().lock(true).open("tests/test1.cdb") {
+        assert_eq!(cdb.get(b"two").unwrap().unwrap(), b"Goodbye");
+    }
+
+    assert!(MapOptions::new().open("tests/nonexistent.cdb").is_err());
+    assert!(
+        MapOptions::new()
+            .format(cdb::Format::Cdb64)
+            .open("tests/test1.cdb")
+            .is_err()
+    );
+}
+
+#[test]
+fn test_residency() {
+    let data = std::fs::read("tests/test1.cdb").unwrap();
+    let cdb = cdb::CDB::new(&data[..]).unwrap();
+    cdb.prefault(Prefault::All).unwrap();
+    let residency = cdb.residency().unwrap();
+    assert_eq!(residency.size, data.len() as u64);
+    assert_eq!(residency.resident, residency.size);
+}
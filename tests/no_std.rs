@@ -0,0 +1,66 @@
+extern crate cdb;
+
+// These tests only use the parts of the crate which are available
+// without the `std` feature, and so also run with
+// `--no-default-features`.
+
+static DATA: &[u8] = include_bytes!("test1.cdb");
+
+// The bare type name is the same reader whatever the features.
+static CDB: cdb::CDB = cdb::CDB::from_static(DATA);
+
+fn count(cdb: &cdb::CDB, key: &[u8]) -> u64 {
+    cdb.count(key).unwrap()
+}
+
+#[test]
+fn test_static() {
+    assert_eq!(count(&CDB, b"one"), 2);
+    let mut i = CDB.find(b"one");
+    assert_eq!(i.next().unwrap().unwrap(), b"Hello");
+    assert_eq!(i.next().unwrap().unwrap(), b", World!");
+    assert!(i.next().is_none());
+    assert_eq!(CDB.get_ref(b"two").unwrap().unwrap(), b"Goodbye");
+    assert!(CDB.get(b"four").is_none());
+    assert_eq!(CDB.iter().count(), 4);
+    assert!(CDB.verify().unwrap().is_ok());
+}
+
+#[test]
+fn test_slice() {
+    let cdb = cdb::CDB::new(DATA).unwrap();
+    assert_eq!(cdb.len().unwrap(), 4);
+    assert_eq!(cdb.stats().unwrap().records, 4);
+    assert!(matches!(
+        cdb::CDB::new(&DATA[..2047]),
+        Err(cdb::Error::Truncated)
+    ));
+}
+
+/// A storage that only holds the first part of a file.
+struct Short(&'static [u8]);
+
+impl cdb::Storage for Short {
+    fn size(&self) -> u64 {
+        DATA.len() as u64
+    }
+
+    fn read_at(&self, buf: &mut [u8], pos: u64) -> cdb::Result<()> {
+        let pos = pos as usize;
+        match self.0.get(pos..pos + buf.len()) {
+            Some(data) => {
+                buf.copy_from_slice(data);
+                Ok(())
+            }
+            None => Err(cdb::Error::Truncated),
+        }
+    }
+}
+
+#[test]
+fn test_custom_storage() {
+    let cdb = cdb::CDB::new(Short(&DATA[..2048])).unwrap();
+    let err = cdb.get(b"one").unwrap().unwrap_err();
+    assert!(matches!(err, cdb::Error::Truncated));
+    assert!(err.is_corrupt());
+}
@@ -0,0 +1,34 @@
+#![cfg(feature = "rayon")]
+extern crate cdb;
+use rayon::prelude::*;
+use std::fs;
+
+#[test]
+fn test_par_iter() {
+    let filename = "tests/parallel.cdb";
+    let mut cdb = cdb::CDBWriter::create(filename).unwrap();
+    for i in 0..10000 {
+        let value = vec![b'x'; i % 37];
+        cdb.add(format!("key{}", i).as_bytes(), &value).unwrap();
+    }
+    cdb.add(b"", b"").unwrap();
+    cdb.finish().unwrap();
+
+    let cdb = cdb::CDB::open(filename).unwrap();
+    let all: Vec<_> = cdb.iter().map(|r| r.unwrap()).collect();
+    let par: Vec<_> = cdb.par_iter().map(|r| r.unwrap()).collect();
+    assert_eq!(par, all);
+    let par: Vec<_> = cdb.par_iter_ref().map(|r| r.unwrap()).collect();
+    assert_eq!(par.len(), all.len());
+    assert!(par.iter().zip(&all).all(|(a, b)| a.0 == b.0 && a.1 == b.1));
+
+    fs::remove_file(filename).unwrap();
+}
+
+#[test]
+fn test_par_iter_small() {
+    let cdb = cdb::CDB::open("tests/test1.cdb").unwrap();
+    let all: Vec<_> = cdb.iter().map(|r| r.unwrap()).collect();
+    let par: Vec<_> = cdb.par_iter().map(|r| r.unwrap()).collect();
+    assert_eq!(par, all);
+}
@@ -1,3 +1,4 @@
+#![cfg(feature = "std")]
 extern crate cdb;
 
 #[test]
@@ -20,3 +21,313 @@ fn test_two() {
         b"Got it."
     );
 }
+
+#[test]
+fn test_ref() {
+    let cdb = cdb::CDB::open("tests/test1.cdb").unwrap();
+    assert_eq!(cdb.get_ref(b"two").unwrap().unwrap(), b"Goodbye");
+    assert!(cdb.get_ref(b"three").is_none());
+    let mut i = cdb.find_ref(b"one");
+    assert_eq!(i.next().unwrap().unwrap(), b"Hello");
+    assert_eq!(i.next().unwrap().unwrap(), b", World!");
+    assert!(i.next().is_none());
+}
+
+#[test]
+fn test_iter_ref() {
+    let cdb = cdb::CDB::open("tests/test1.cdb").unwrap();
+    let refs: Vec<_> = cdb.iter_ref().map(|r| r.unwrap()).collect();
+    let owned: Vec<_> = cdb.iter().map(|r| r.unwrap()).collect();
+    assert_eq!(refs.len(), 4);
+    assert_eq!(refs[0], (&b"one"[..], &b"Hello"[..]));
+    for (r, o) in refs.iter().zip(owned.iter()) {
+        assert_eq!(r.0, &o.0[..]);
+        assert_eq!(r.1, &o.1[..]);
+    }
+}
+
+#[test]
+fn test_memory() {
+    static DATA: &[u8] = include_bytes!("test1.cdb");
+    let cdb = cdb::CDB::new(DATA).unwrap();
+    assert_eq!(cdb.get_ref(b"two").unwrap().unwrap(), b"Goodbye");
+
+    let cdb = cdb::CDB::new(DATA.to_vec()).unwrap();
+    let mut i = cdb.find(b"one");
+    assert_eq!(i.next().unwrap().unwrap(), b"Hello");
+    assert_eq!(i.next().unwrap().unwrap(), b", World!");
+    assert!(i.next().is_none());
+
+    let cdb = cdb::CDB::new(DATA.to_vec().into_boxed_slice()).unwrap();
+    assert_eq!(cdb.iter().count(), 4);
+
+    assert!(cdb::CDB::new(&DATA[..2047]).is_err());
+}
+
+#[test]
+fn test_pread() {
+    let file = cdb::PreadFile::open("tests/test1.cdb").unwrap();
+    let cdb = cdb::CDB::new(file).unwrap();
+    let mut i = cdb.find(b"one");
+    assert_eq!(i.next().unwrap().unwrap(), b"Hello");
+    assert_eq!(i.next().unwrap().unwrap(), b", World!");
+    assert!(i.next().is_none());
+    assert_eq!(cdb.iter().count(), 4);
+}
+
+/// A storage that inverts every byte, standing in for an encoded store.
+struct Inverted(Vec<u8>);
+
+impl cdb::Storage for Inverted {
+    fn size(&self) -> u64 {
+        self.0.len() as u64
+    }
+
+    fn read_at(&self, buf: &mut [u8], pos: u64) -> cdb::Result<()> {
+        let pos = pos as usize;
+        let end = pos + buf.len();
+        for (dst, src) in buf.iter_mut().zip(&self.0[pos..end]) {
+            *dst = !src;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_custom_storage() {
+    let data = std::fs::read("tests/test1.cdb").unwrap();
+    let cdb = cdb::CDB::new(Inverted(data.iter().map(|b| !b).collect())).unwrap();
+    assert_eq!(cdb.get(b"two").unwrap().unwrap(), b"Goodbye");
+    let (key, value) = cdb.iter().next().unwrap().unwrap();
+    assert_eq!(key, b"one");
+    assert_eq!(value, b"Hello");
+}
+
+#[test]
+fn test_pread_truncated() {
+    let filename = "tests/pread-truncated.cdb";
+    std::fs::copy("tests/test1.cdb", filename).unwrap();
+    let cdb = cdb::CDB::open_pread(filename).unwrap();
+    let mut value = Vec::new();
+    assert!(cdb.get_into(b"two", &mut value).unwrap());
+    assert_eq!(value, b"Goodbye");
+    assert!(!cdb.get_into(b"three", &mut value).unwrap());
+
+    std::fs::OpenOptions::new()
+        .write(true)
+        .open(filename)
+        .unwrap()
+        .set_len(2048)
+        .unwrap();
+    let err = cdb.get_into(b"two", &mut value).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+    assert!(cdb.iter().next().unwrap().is_err());
+    std::fs::remove_file(filename).unwrap();
+}
+
+#[test]
+fn test_cursor() {
+    let cdb = cdb::CDB::open("tests/test1.cdb").unwrap();
+    let all: Vec<_> = cdb.iter().map(|r| r.unwrap()).collect();
+    let mut iter = cdb.iter();
+    for i in 0..=all.len() {
+        let cursor = cdb::Cursor::new(iter.cursor().offset());
+        let rest: Vec<_> = cdb.iter_from(cursor).unwrap().map(|r| r.unwrap()).collect();
+        assert_eq!(rest, &all[i..]);
+        let rest = cdb.iter_ref_from(cursor).unwrap().count();
+        assert_eq!(rest, all.len() - i);
+        iter.next();
+    }
+    // At the end, the cursor points at the first hash table.
+    let data = std::fs::read("tests/test1.cdb").unwrap();
+    let data_end = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+    assert_eq!(iter.cursor().offset(), data_end as u64);
+
+    for offset in [0, 2047, 2049, 2060, 1 << 40] {
+        assert!(cdb.iter_from(cdb::Cursor::new(offset)).is_err());
+    }
+}
+
+#[test]
+fn test_get_many() {
+    let keys: Vec<&[u8]> = vec![
+        b"two",
+        b"one",
+        b"four",
+        b"this key will be split across two reads",
+        b"one",
+        b"",
+    ];
+    let cdb = cdb::CDB::open("tests/test1.cdb").unwrap();
+    let values = cdb.get_many(&keys);
+    assert_eq!(values.len(), keys.len());
+    for (key, value) in keys.iter().zip(values) {
+        let expected = cdb.get(key).map(|r| r.unwrap());
+        assert_eq!(value.map(|r| r.unwrap()), expected);
+    }
+
+    let cdb = cdb::CDB::open_pread("tests/test1.cdb").unwrap();
+    let values = cdb.get_many(&[b"two"]);
+    assert_eq!(values[0].as_ref().unwrap().as_ref().unwrap(), b"Goodbye");
+    assert_eq!(
+        cdb.get_with_hash(b"two", cdb::CDBHash::hash(cdb.hasher(), b"two"))
+            .unwrap()
+            .unwrap(),
+        b"Goodbye"
+    );
+    assert!(cdb.get_with_hash(b"two", 0).is_none());
+}
+
+/// A hash function under which keys of the same length collide.
+struct LenHash;
+
+impl cdb::CDBHash for LenHash {
+    fn hash(&self, key: &[u8]) -> u32 {
+        key.len() as u32
+    }
+
+    fn id(&self) -> Option<[u8; 8]> {
+        Some(*b"test-len")
+    }
+}
+
+#[test]
+fn test_get_many_collisions() {
+    let mut db = cdb::CDBBuffer::with_hasher(cdb::Format::Cdb32, LenHash);
+    for (key, value) in [(b"ab", b"1"), (b"cd", b"2"), (b"ef", b"3"), (b"cd", b"4")] {
+        db.add(key, value).unwrap();
+    }
+    let data = db.finish().unwrap();
+    let cdb = cdb::CDB::with_hasher(&data[..], cdb::Format::Cdb32, LenHash).unwrap();
+    let values = cdb.get_many(&[b"ef", b"ab", b"gh", b"cd"]);
+    let values: Vec<_> = values.into_iter().map(|v| v.map(Result::unwrap)).collect();
+    assert_eq!(
+        values,
+        [
+            Some(b"3".to_vec()),
+            Some(b"1".to_vec()),
+            None,
+            Some(b"2".to_vec())
+        ]
+    );
+}
+
+#[test]
+fn test_metadata() {
+    let cdb = cdb::CDB::open_pread("tests/test1.cdb").unwrap();
+    assert!(cdb.contains_key(b"one").unwrap());
+    assert!(
+        cdb.contains_key(b"this key will be split across two reads")
+            .unwrap()
+    );
+    assert!(!cdb.contains_key(b"four").unwrap());
+    assert_eq!(cdb.count(b"one").unwrap(), 2);
+    assert_eq!(cdb.count(b"two").unwrap(), 1);
+    assert_eq!(cdb.count(b"four").unwrap(), 0);
+    let lens: Vec<u64> = cdb.value_lens(b"one").map(|r| r.unwrap()).collect();
+    assert_eq!(lens, vec![5, 8]);
+    assert_eq!(cdb.len().unwrap(), 4);
+    assert!(!cdb.is_empty().unwrap());
+}
+
+#[test]
+fn test_value_reader() {
+    use std::io::{Read, Seek, SeekFrom};
+
+    let cdb = cdb::CDB::open_pread("tests/test1.cdb").unwrap();
+    let mut reader = cdb.get_reader(b"one").unwrap().unwrap();
+    assert_eq!(reader.len(), 5);
+    let mut buf = [0; 3];
+    reader.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"Hel");
+    assert_eq!(reader.seek(SeekFrom::End(-2)).unwrap(), 3);
+    let mut rest = Vec::new();
+    reader.read_to_end(&mut rest).unwrap();
+    assert_eq!(rest, b"lo");
+    assert_eq!(reader.seek(SeekFrom::Current(10)).unwrap(), 15);
+    assert_eq!(reader.read(&mut buf).unwrap(), 0);
+    assert!(reader.seek(SeekFrom::Current(-16)).is_err());
+    assert!(cdb.get_reader(b"four").is_none());
+
+    let mut values = cdb.find(b"one");
+    values.next_reader().unwrap().unwrap();
+    let mut value = String::new();
+    let mut reader = values.next_reader().unwrap().unwrap();
+    reader.read_to_string(&mut value).unwrap();
+    assert_eq!(value, ", World!");
+    assert!(values.next_reader().is_none());
+}
+
+#[test]
+fn test_value_reader_corrupt() {
+    let mut data = std::fs::read("tests/test1.cdb").unwrap();
+    // Make the length of the first value enormous.
+    data[2048 + 4..2048 + 8].copy_from_slice(&[0xff, 0xff, 0xff, 0x7f]);
+    let cdb = cdb::CDB::new(&data[..]).unwrap();
+    assert!(cdb.get_reader(b"one").unwrap().is_err());
+}
+
+#[test]
+fn test_offsets() {
+    let cdb = cdb::CDB::open("tests/test1.cdb").unwrap();
+    let offsets: Vec<u64> = cdb.iter_offsets().map(|r| r.unwrap()).collect();
+    let records: Vec<_> = cdb.iter().map(|r| r.unwrap()).collect();
+    assert_eq!(offsets.len(), records.len());
+    for (offset, record) in offsets.iter().zip(&records) {
+        assert_eq!(&cdb.record_at(*offset).unwrap(), record);
+        let (key, value) = cdb.record_ref_at(*offset).unwrap();
+        assert_eq!((key, value), (&record.0[..], &record.1[..]));
+    }
+
+    let found: Vec<u64> = cdb.find_offsets(b"one").map(|r| r.unwrap()).collect();
+    assert_eq!(found.len(), 2);
+    assert_eq!(found[0], offsets[0]);
+    assert_eq!(cdb.record_at(found[1]).unwrap().1, b", World!");
+    assert!(cdb.find_offsets(b"four").next().is_none());
+
+    for offset in [0, 2047, 1 << 40] {
+        assert!(cdb.record_at(offset).is_err());
+    }
+}
+
+#[test]
+fn test_errors() {
+    use cdb::Error;
+
+    let data = std::fs::read("tests/test1.cdb").unwrap();
+    let err = cdb::CDB::new(&data[..2000]).err().unwrap();
+    assert!(matches!(err, Error::Truncated));
+    assert!(err.is_corrupt());
+    assert!(matches!(
+        cdb::Format::detect(&vec![0xff; 5000]),
+        Err(Error::UnknownFormat)
+    ));
+    assert!(matches!(
+        cdb::CDB::with_hasher(&data[..], cdb::Format::Cdb32, cdb::Xxh32Hash::new(1)),
+        Err(Error::HashMismatch)
+    ));
+
+    let cdb = cdb::CDB::new(&data[..]).unwrap();
+    assert!(matches!(
+        cdb.record_at(1 << 40),
+        Err(Error::OutOfRange { offset }) if offset == 1 << 40
+    ));
+    assert!(matches!(
+        cdb.iter_from(cdb::Cursor::new(2049)),
+        Err(Error::InvalidCursor { offset: 2049 })
+    ));
+
+    let mut data = data.clone();
+    data[2048 + 4..2048 + 8].copy_from_slice(&[0xff, 0xff, 0xff, 0x7f]);
+    let cdb = cdb::CDB::new(&data[..]).unwrap();
+    let err = cdb.iter().next().unwrap().err().unwrap();
+    assert!(matches!(err, Error::RecordPastEnd { offset: 2048 }));
+    assert!(err.is_corrupt());
+    let err = std::io::Error::from(err);
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+    let err = cdb::CDB::open("tests/nonexistent.cdb").err().unwrap();
+    assert!(matches!(err, Error::Io(_)));
+    assert!(!err.is_corrupt());
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+}
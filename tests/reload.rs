@@ -0,0 +1,33 @@
+#![cfg(all(unix, feature = "std"))]
+extern crate cdb;
+use cdb::ReloadingCDB;
+
+fn make(filename: &str, value: &[u8]) {
+    let mut cdb = cdb::CDBWriter::create(filename).unwrap();
+    cdb.add(b"key", value).unwrap();
+    cdb.finish().unwrap();
+}
+
+#[test]
+fn test_reload() {
+    let filename = "tests/reload.cdb";
+    make(filename, b"old");
+    let db = ReloadingCDB::open(filename).unwrap();
+    assert!(!db.reload().unwrap());
+
+    let old = db.cdb();
+    make(filename, b"new");
+    assert!(db.reload().unwrap());
+    assert!(!db.reload().unwrap());
+    // The snapshot taken before the reload still reads the old file.
+    assert_eq!(old.get(b"key").unwrap().unwrap(), b"old");
+    assert_eq!(db.cdb().get(b"key").unwrap().unwrap(), b"new");
+
+    // A broken replacement leaves the current database in place.
+    std::fs::write("tests/reload.junk", b"junk").unwrap();
+    std::fs::rename("tests/reload.junk", filename).unwrap();
+    assert!(db.reload().is_err());
+    assert_eq!(db.cdb().get(b"key").unwrap().unwrap(), b"new");
+
+    std::fs::remove_file(filename).unwrap();
+}
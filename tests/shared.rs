@@ -0,0 +1,38 @@
+#![cfg(feature = "std")]
+extern crate cdb;
+use cdb::SharedCDB;
+use std::thread;
+
+#[test]
+fn test_shared() {
+    let cdb = SharedCDB::open("tests/test1.cdb").unwrap();
+    let values: Vec<_> = cdb.find(b"one").map(|r| r.unwrap()).collect();
+    assert_eq!(values.len(), 2);
+    assert_eq!(values[0], *b"Hello");
+    assert_eq!(values[1], *b", World!");
+    assert_eq!(values[1].slice(2..7), *b"World");
+    assert!(cdb.get(b"four").is_none());
+
+    // Values outlive the handle they came from.
+    let value = cdb.get(b"two").unwrap().unwrap();
+    drop(cdb);
+    assert_eq!(&value[..], b"Goodbye");
+    assert_eq!(value.clone(), value);
+}
+
+#[test]
+fn test_shared_threads() {
+    let cdb = SharedCDB::new(cdb::CDB::new(std::fs::read("tests/test1.cdb").unwrap()).unwrap());
+    let values = cdb.find(b"one");
+    let records = cdb.iter();
+    let values = thread::spawn(move || values.map(|r| r.unwrap().to_vec()).collect::<Vec<_>>());
+    let records = thread::spawn(move || records.map(|r| r.unwrap()).collect::<Vec<_>>());
+    assert_eq!(
+        values.join().unwrap(),
+        vec![b"Hello".to_vec(), b", World!".to_vec()]
+    );
+    let records = records.join().unwrap();
+    assert_eq!(records.len(), 4);
+    assert_eq!(records[0].0, *b"one");
+    assert_eq!(records[0].1, *b"Hello");
+}
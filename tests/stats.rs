@@ -0,0 +1,66 @@
+#![cfg(feature = "std")]
+extern crate cdb;
+use cdb::RecordSize;
+use std::fs;
+
+#[test]
+fn test_stats() {
+    let filename = "tests/stats.cdb";
+    let mut cdb = cdb::CDBWriter::create(filename).unwrap();
+    cdb.add(b"a", b"1").unwrap();
+    cdb.add(b"a", b"2").unwrap();
+    cdb.add(b"b", b"333").unwrap();
+    cdb.add(b"c", b"").unwrap();
+    cdb.finish().unwrap();
+
+    let cdb = cdb::CDB::open(filename).unwrap();
+    let stats = cdb.stats().unwrap();
+    assert_eq!(stats.records, 4);
+    assert_eq!(stats.distinct_keys, 3);
+    assert_eq!(stats.multi_value_keys, 1);
+    assert_eq!(stats.key_bytes, 4);
+    assert_eq!(stats.value_bytes, 5);
+
+    assert_eq!(stats.tables.len(), 256);
+    assert_eq!(stats.tables.iter().map(|t| t.slots).sum::<u64>(), 8);
+    assert_eq!(stats.tables.iter().map(|t| t.used).sum::<u64>(), 4);
+    let table = stats.tables.iter().find(|t| t.used > 0).unwrap();
+    assert_eq!(table.load_factor(), 0.5);
+    assert_eq!(stats.probes.iter().sum::<u64>(), 4);
+
+    assert_eq!(stats.largest.len(), 4);
+    assert_eq!(
+        stats.largest[0],
+        RecordSize {
+            offset: 2048 + 2 * 10,
+            key_len: 1,
+            value_len: 3,
+        }
+    );
+    assert_eq!(stats.largest[3].value_len, 0);
+
+    fs::remove_file(filename).unwrap();
+}
+
+#[test]
+fn test_stats_many() {
+    let filename = "tests/stats-many.cdb";
+    let mut cdb = cdb::CDBWriter::create(filename).unwrap();
+    for i in 0..1000 {
+        cdb.add(format!("{}", i % 300).as_bytes(), &vec![b'x'; i])
+            .unwrap();
+    }
+    cdb.finish().unwrap();
+
+    let cdb = cdb::CDB::open(filename).unwrap();
+    let stats = cdb.stats().unwrap();
+    assert_eq!(stats.records, 1000);
+    assert_eq!(stats.distinct_keys, 300);
+    assert_eq!(stats.multi_value_keys, 300);
+    assert_eq!(stats.probes.iter().sum::<u64>(), 1000);
+    assert_eq!(stats.largest.len(), 10);
+    assert_eq!(stats.largest[0].value_len, 999);
+    assert_eq!(stats.largest[9].value_len, 990);
+
+    fs::remove_file(filename).unwrap();
+}
@@ -0,0 +1,159 @@
+#![cfg(feature = "std")]
+extern crate cdb;
+use cdb::{CDB, CDBHash, DjbHash, Format, Problem, ProblemKind};
+use std::fs;
+
+fn make(filename: &str) -> Vec<u8> {
+    let mut cdb = cdb::CDBWriter::create(filename).unwrap();
+    cdb.add(b"one", b"Hello").unwrap();
+    cdb.add(b"two", b"Goodbye").unwrap();
+    cdb.add(b"one", b", World!").unwrap();
+    cdb.finish().unwrap();
+    let data = fs::read(filename).unwrap();
+    fs::remove_file(filename).unwrap();
+    data
+}
+
+fn u32_at(data: &[u8], pos: usize) -> usize {
+    u32::from_le_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]) as usize
+}
+
+/// Find the offset of the slot pointing at the first record.
+fn first_slot(data: &[u8]) -> usize {
+    let table = (DjbHash.hash(b"one") & 0xff) as usize * 8;
+    let (hpos, hslots) = (u32_at(data, table), u32_at(data, table + 4));
+    (0..hslots)
+        .map(|i| hpos + i * 8)
+        .find(|&slot| u32_at(data, slot + 4) == 2048)
+        .unwrap()
+}
+
+fn problems(data: &[u8]) -> Vec<Problem> {
+    CDB::new(data).unwrap().verify().unwrap().problems
+}
+
+#[test]
+fn test_valid() {
+    let report = CDB::open("tests/test1.cdb").unwrap().verify().unwrap();
+    assert!(report.is_ok());
+    assert_eq!(report.records, 4);
+
+    let report = CDB::new(make("tests/verify-valid.cdb"))
+        .unwrap()
+        .verify()
+        .unwrap();
+    assert!(report.is_ok());
+    assert_eq!(report.records, 3);
+
+    let filename = "tests/verify-valid-64.cdb";
+    let mut cdb =
+        cdb::CDBWriter::with_hasher(filename, Format::Cdb64, cdb::Xxh32Hash::new(3)).unwrap();
+    for i in 0..1000 {
+        cdb.add(format!("{}", i).as_bytes(), b"value").unwrap();
+    }
+    cdb.finish().unwrap();
+    let data = fs::read(filename).unwrap();
+    fs::remove_file(filename).unwrap();
+    let cdb = CDB::with_hasher(&data, Format::Cdb64, cdb::Xxh32Hash::new(3)).unwrap();
+    let report = cdb.verify().unwrap();
+    assert!(report.is_ok(), "{:?}", report.problems);
+    assert_eq!(report.records, 1000);
+}
+
+#[test]
+fn test_hash_mismatch() {
+    let mut data = make("tests/verify-hash.cdb");
+    let slot = first_slot(&data);
+    data[slot + 1] ^= 1;
+    let actual = DjbHash.hash(b"one");
+    assert_eq!(
+        problems(&data),
+        vec![Problem {
+            offset: slot as u64,
+            kind: ProblemKind::HashMismatch {
+                stored: (actual ^ 0x100) as u64,
+                actual
+            },
+        }]
+    );
+}
+
+#[test]
+fn test_unreachable() {
+    let mut data = make("tests/verify-unreachable.cdb");
+    let slot = first_slot(&data);
+    data[slot..slot + 8].copy_from_slice(&[0; 8]);
+    let found = problems(&data);
+    assert!(found.contains(&Problem {
+        offset: 2048,
+        kind: ProblemKind::RecordUnreachable,
+    }));
+    // The empty slot also cuts off the probe sequence for the second
+    // record with the same key.
+    assert!(
+        found
+            .iter()
+            .any(|problem| problem.kind == ProblemKind::SlotUnreachable)
+    );
+}
+
+#[test]
+fn test_misaligned() {
+    let mut data = make("tests/verify-misaligned.cdb");
+    let slot = first_slot(&data);
+    data[slot + 4] = 1;
+    let found = problems(&data);
+    assert!(found.contains(&Problem {
+        offset: slot as u64,
+        kind: ProblemKind::SlotMisaligned { pos: 2049 },
+    }));
+    assert!(found.contains(&Problem {
+        offset: 2048,
+        kind: ProblemKind::RecordUnreachable,
+    }));
+}
+
+#[test]
+fn test_record_out_of_bounds() {
+    let mut data = make("tests/verify-record.cdb");
+    data[2048 + 5] = 1;
+    let found = problems(&data);
+    assert_eq!(
+        found[0],
+        Problem {
+            offset: 2048,
+            kind: ProblemKind::RecordOutOfBounds,
+        }
+    );
+}
+
+#[test]
+fn test_table_overlap() {
+    let mut data = make("tests/verify-table.cdb");
+    let table = (DjbHash.hash(b"one") & 0xff) as usize * 8;
+    let records_end = u32_at(&data, 0) as u32;
+    data[table..table + 4].copy_from_slice(&(records_end - 8).to_le_bytes());
+    assert!(problems(&data).contains(&Problem {
+        offset: table as u64,
+        kind: ProblemKind::TableOverlapsRecords,
+    }));
+}
+
+#[test]
+fn test_trailing_bytes() {
+    let mut data = make("tests/verify-trailing.cdb");
+    let len = data.len() as u64;
+    data.extend_from_slice(b"junk");
+    let found = problems(&data);
+    assert_eq!(
+        found,
+        vec![Problem {
+            offset: len,
+            kind: ProblemKind::TrailingBytes,
+        }]
+    );
+    assert_eq!(
+        found[0].to_string(),
+        format!("offset {}: unexpected bytes after the hash tables", len)
+    );
+}